@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+
+pub struct AnimationPlugin;
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<AnimationSpeed>()
+            .add_systems(Update,
+                animate.run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor)))
+            );
+    }
+}
+
+// Constants
+
+const BOUNCE_HEIGHT: f32 = 0.15;
+const DROP_DURATION: f32 = 0.3;
+const DROP_HEIGHT: f32 = 2.0;
+const HOP_DURATION: f32 = 0.4;
+const HOP_HEIGHT: f32 = 1.0;
+const SETTLE_DURATION: f32 = 0.45;
+
+// Structs
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TweenKind {
+    // Blocks fall into place
+    Drop,
+    // Workers jump along an arc
+    Hop,
+    // Domes fall and bounce once
+    Settle,
+}
+
+// Resources
+
+#[derive(Clone, Copy, Default, PartialEq, Resource)]
+pub enum AnimationSpeed {
+    #[default]
+    Normal,
+    Fast,
+    Off,
+}
+
+// Components
+
+#[derive(Component)]
+pub struct Tween {
+    duration: f32,
+    elapsed: f32,
+    from: Vec3,
+    kind: TweenKind,
+    to: Vec3,
+}
+
+impl Tween {
+    // None when animations are turned off
+    pub fn new(kind: TweenKind, from: Vec3, to: Vec3, speed: AnimationSpeed) -> Option<Self> {
+        let duration = match kind {
+            TweenKind::Drop => DROP_DURATION,
+            TweenKind::Hop => HOP_DURATION,
+            TweenKind::Settle => SETTLE_DURATION,
+        };
+        let duration = match speed {
+            AnimationSpeed::Normal => duration,
+            AnimationSpeed::Fast => duration * 0.4,
+            AnimationSpeed::Off => return None,
+        };
+
+        Some(Self { duration, elapsed: 0.0, from, kind, to })
+    }
+    pub fn fall(kind: TweenKind, to: Vec3, speed: AnimationSpeed) -> Option<Self> {
+        Self::new(kind, to + Vec3::Y * DROP_HEIGHT, to, speed)
+    }
+    pub fn translation(&self) -> Vec3 {
+        let t = (self.elapsed / self.duration).min(1.0);
+        match self.kind {
+            TweenKind::Drop => self.from.lerp(self.to, t * t),
+            TweenKind::Hop => self.from.lerp(self.to, t) + Vec3::Y * HOP_HEIGHT * 4.0 * t * (1.0 - t),
+            // Falls for most of the time, then bounces back up a little
+            TweenKind::Settle if t < 0.7 => self.from.lerp(self.to, (t / 0.7).powi(2)),
+            TweenKind::Settle => {
+                let t = (t - 0.7) / 0.3;
+                self.to + Vec3::Y * BOUNCE_HEIGHT * 4.0 * t * (1.0 - t)
+            }
+        }
+    }
+}
+
+// Systems
+
+fn animate(
+    mut commands: Commands,
+    mut tween_query: Query<(Entity, &mut Transform, &mut Tween)>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut tween) in tween_query.iter_mut() {
+        tween.elapsed += time.delta_seconds();
+        transform.translation = tween.translation();
+
+        if tween.elapsed >= tween.duration {
+            commands.entity(entity).remove::<Tween>();
+        }
+    }
+}
\ No newline at end of file
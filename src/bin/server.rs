@@ -0,0 +1,7 @@
+use rusty_santorini::network::{DEFAULT_PORT, server};
+
+// A headless server pairing players on the local network, "server [port]"
+fn main() {
+    let port = std::env::args().nth(1).and_then(|x| x.parse().ok()).unwrap_or(DEFAULT_PORT);
+    server::run(port);
+}
\ No newline at end of file
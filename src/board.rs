@@ -1,71 +1,333 @@
 use bevy::prelude::*;
 
 use bevy::{
-    input::mouse::MouseMotion,
-    utils::hashbrown::HashSet,
+    asset::io::file::FileAssetReader,
+    pbr::NotShadowCaster,
+    utils::hashbrown::{HashMap, HashSet},
 };
+use bevy_mod_picking::prelude::Pickable;
 use itertools::Itertools;
 
-use crate::AppState;
+use crate::{
+    AppState,
+    animation::{AnimationSpeed, Tween, TweenKind},
+    camera::BoardCamera,
+    theme::Theme,
+};
 
 pub struct BoardPlugin;
 impl Plugin for BoardPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<Rules>()
             .add_systems(OnEnter(AppState::InGame),
                 setup
             )
-            .add_systems(PreUpdate,
-                camera_input.run_if(in_state(AppState::InGame))
+            .add_systems(OnEnter(AppState::Editor),
+                setup
+            )
+            .add_systems(Update,
+                check_win.run_if(in_state(AppState::InGame))
             )
             .add_systems(Update,
                 (
-                    check_win,
-                    update_camera,
                     update_board,
-                ).run_if(in_state(AppState::InGame))
+                    update_ghosts,
+                    update_last_move,
+                    update_preview,
+                    update_supply_text,
+                ).run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor)))
             )
             .add_systems(OnExit(AppState::InGame),
                 cleanup
+            )
+            .add_systems(OnExit(AppState::Editor),
+                cleanup
             );
     }
 }
 
 // Structs
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Action {
+    pub from: (usize, usize, usize),
+    pub to: (usize, usize, usize),
+    pub build: Option<(usize, usize, usize)>,
+    pub dome: bool,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum FirstPlayer {
+    #[default]
+    Gold,
+    Silver,
+    Random,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GameResult {
+    // None when the game was drawn
+    pub winner: Option<Turn>,
+    pub reason: WinReason,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Ply {
+    Action(Action),
+    Placement {
+        row: usize,
+        column: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Piece {
     Block,
     Board,
+    Dome,
     Worker {
         turn: Turn
     },
 }
 
-#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum PlacementOrder {
+    #[default]
+    Standard,
+    Alternating,
+    StarterLast,
+}
+
+// Base times, increments and periods are in seconds
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimeControl {
+    #[default]
+    Unlimited,
+    Fischer {
+        base: u64,
+        increment: u64,
+    },
+    ByoYomi {
+        base: u64,
+        period: u64,
+        periods: u32,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 pub enum Turn {
     #[default]
     P1,
     P2,
     WinP1,
     WinP2,
+    Draw,
+}
+impl Turn {
+    pub fn opponent(self) -> Self {
+        match self {
+            Turn::P1 => Turn::P2,
+            Turn::P2 => Turn::P1,
+            Turn::WinP1 => Turn::WinP2,
+            Turn::WinP2 => Turn::WinP1,
+            Turn::Draw => Turn::Draw,
+        }
+    }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WinReason {
+    // Both players agreed to a draw
+    Agreement,
+    // The loser's opponent got blocked, most often because their workers can't move
+    Blocked,
+    // The loser's connection or bot went away
+    Disconnect,
+    // The loser broke a rule, like a bot playing an illegal ply
+    Forfeit,
+    Resignation,
+    Timeout,
+    Tower,
+}
+
+// Constants
+
+// Where the top face of the ground and of each level is above the piece's origin, and how wide it is
+const TOP_FACES: [(f32, f32); 4] = [(0.05, 1.0), (1.0, 0.95), (0.8, 0.85), (0.6, 0.8)];
+// Models are looked up by name in here, see load_model
+const MODELS_DIRECTORY: &str = "assets/models";
+pub const PIECE_SUPPLY: [usize ; 4] = [22, 18, 14, 18];
+// Opposite corners of the plinth under the board, which takes the colour of the player to move
+pub const TURN_INDICATOR: (Vec3, Vec3) = (Vec3::new(-2.9, -0.21, -2.9), Vec3::new(2.9, -0.49, 2.9));
+
 // Resources
 
-#[derive(Resource)]
+#[derive(Clone, Resource)]
+#[allow(clippy::type_complexity)]
 pub struct Board {
     data: [[[Option<Piece> ; 5] ; 5] ; 5],
+    domes_anywhere: bool,
+    history: Vec<Ply>,
+    pending: Option<((usize, usize, usize), (usize, usize, usize))>,
+    placements: Vec<Turn>,
+    result: Option<GameResult>,
+    starter: Turn,
+    supply: Option<[usize ; 4]>,
     turn: Turn,
 }
 impl Board {
+    pub fn new(rules: &Rules) -> Self {
+        let starter = match rules.first_player {
+            FirstPlayer::Gold => Turn::P1,
+            FirstPlayer::Silver => Turn::P2,
+            FirstPlayer::Random => if fastrand::bool() { Turn::P1 } else { Turn::P2 },
+        };
+
+        let mut board = Self::default();
+        board.apply_rules(starter, rules);
+        board
+    }
+    // Pieces already on the board are taken out of a limited supply, so that set up positions can be
+    // played under the rules too
+    pub fn apply_rules(&mut self, starter: Turn, rules: &Rules) {
+        let mut supply = PIECE_SUPPLY;
+        for PieceMarker { piece, height, .. } in self.get_pieces() {
+            match piece {
+                Piece::Block => supply[height - 1] = supply[height - 1].saturating_sub(1),
+                Piece::Dome => supply[3] = supply[3].saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        self.domes_anywhere = rules.domes_anywhere;
+        self.pending = None;
+        self.supply = rules.limited_supply.then_some(supply);
+        self.start_placement(starter, rules.placement_order);
+    }
     pub fn build(&mut self, row: usize, column: usize, height: usize) {
+        if height == 4 {
+            self.build_dome(row, column, height);
+            return;
+        }
         if self.data[row][column][height].is_some() {
             panic!("Can't build on ({}, {}, {}) because it's already occupied!", row, column, height);
         }
+        if let Some(supply) = self.supply.as_mut() {
+            if supply[height - 1] == 0 {
+                panic!("Can't build on ({}, {}, {}) because there are no level {} pieces left!", row, column, height, height);
+            }
+            supply[height - 1] -= 1;
+        }
 
         self.data[row][column][height] = Some(Piece::Block);
     }
+    pub fn build_dome(&mut self, row: usize, column: usize, height: usize) {
+        if self.data[row][column][height].is_some() {
+            panic!("Can't build a dome on ({}, {}, {}) because it's already occupied!", row, column, height);
+        }
+        if let Some(supply) = self.supply.as_mut() {
+            if supply[3] == 0 {
+                panic!("Can't build a dome on ({}, {}, {}) because there are no domes left!", row, column, height);
+            }
+            supply[3] -= 1;
+        }
+
+        self.data[row][column][height] = Some(Piece::Dome);
+    }
+    // The position a finished game is analyzed from in the editor, with the loser to move. Nothing is
+    // left of the game's history, placements or piece supply, which Play works out again
+    pub fn analysis(&self) -> Self {
+        let mut board = self.clone();
+        board.clear_history();
+        board.set_placements(self.starter, Vec::new());
+        board.set_supply(None);
+        board.set_turn(self.result.and_then(|x| x.winner).map_or(Turn::P1, |x| x.opponent()));
+        board
+    }
+    pub fn can_build(&self, row: usize, column: usize) -> bool {
+        self.can_build_block(row, column) || self.can_build_dome(row, column)
+    }
+    pub fn can_build_block(&self, row: usize, column: usize) -> bool {
+        match self.get_top(row, column) {
+            Some(top_height) if top_height < 3 => self.get_supply(top_height + 1).is_none_or(|x| x > 0),
+            _ => false,
+        }
+    }
+    pub fn can_build_dome(&self, row: usize, column: usize) -> bool {
+        match self.get_top(row, column) {
+            Some(top_height) if top_height == 3 || self.domes_anywhere => self.get_supply(4).is_none_or(|x| x > 0),
+            _ => false,
+        }
+    }
+    pub fn get_actions(&self) -> Vec<Action> {
+        let mut actions = Vec::new();
+        for PieceMarker { piece, row, column, height } in self.get_pieces() {
+            if piece != (Piece::Worker { turn: self.turn }) {
+                continue;
+            }
+
+            for (to_row, to_column) in neighbours(row, column) {
+                let Some(top_height) = self.get_top(to_row, to_column).filter(|x| *x <= height) else {
+                    continue;
+                };
+                let to = (to_row, to_column, top_height + 1);
+
+                if to.2 == 4 {
+                    actions.push(Action { from: (row, column, height), to, build: None, dome: false });
+                    continue;
+                }
+
+                let mut moved = self.clone();
+                moved.movement(row, column, height, to.0, to.1, to.2);
+                for (build_row, build_column) in neighbours(to.0, to.1) {
+                    for dome in [false, true] {
+                        let can_build = if dome {
+                            moved.can_build_dome(build_row, build_column)
+                        } else {
+                            moved.can_build_block(build_row, build_column)
+                        };
+                        if can_build {
+                            let build_height = moved.get_top(build_row, build_column).unwrap() + 1;
+                            actions.push(Action {
+                                from: (row, column, height),
+                                to,
+                                build: Some((build_row, build_column, build_height)),
+                                dome,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        actions
+    }
+    // Ends the game once a worker stands on the third level or all of a player's workers are stuck
+    pub fn check_win(&mut self) {
+        if let Some((winner, reason)) = self.find_win() {
+            self.win(winner, reason);
+        }
+    }
+    // Unlike forfeits, concessions can come from either player at any time
+    pub fn concede(&mut self, turn: Turn, reason: WinReason) {
+        if let Turn::P1 | Turn::P2 = self.turn {
+            self.win(turn.opponent(), reason);
+        }
+    }
+    pub fn draw(&mut self) {
+        if let Turn::P1 | Turn::P2 = self.turn {
+            self.pending = None;
+            self.result = Some(GameResult { winner: None, reason: WinReason::Agreement });
+            self.turn = Turn::Draw;
+        }
+    }
+    pub fn forfeit(&mut self, reason: WinReason) {
+        self.concede(self.turn, reason);
+    }
+    // Blocks in the tower, without the dome or worker that may be on top of them
+    pub fn get_levels(&self, row: usize, column: usize) -> usize {
+        (1..4).take_while(|x| self.data[row][column][*x] == Some(Piece::Block)).count()
+    }
     pub fn get_piece(&self, row: usize, column: usize, height: usize) -> Option<&Piece> {
         self.data[row][column][height].as_ref()
     }
@@ -83,6 +345,21 @@ impl Board {
         }
         pieces
     }
+    pub fn get_domes_anywhere(&self) -> bool {
+        self.domes_anywhere
+    }
+    pub fn get_placements(&self) -> &[Turn] {
+        &self.placements
+    }
+    pub fn get_result(&self) -> Option<GameResult> {
+        self.result
+    }
+    pub fn get_starter(&self) -> Turn {
+        self.starter
+    }
+    pub fn get_supply(&self, height: usize) -> Option<usize> {
+        self.supply.map(|supply| supply[height - 1])
+    }
     pub fn get_top(&self, row: usize, column: usize) -> Option<usize> {        
         for height in 1..5 {
             match self.data[row][column][height] {
@@ -118,6 +395,70 @@ impl Board {
             _ => self.turn,
         };
     }
+    pub fn move_worker(&mut self, from: (usize, usize, usize), to: (usize, usize, usize)) {
+        if self.pending.is_some() {
+            panic!("Can't move from ({}, {}, {}) because a worker has already moved this turn!", from.0, from.1, from.2);
+        }
+
+        self.movement(from.0, from.1, from.2, to.0, to.1, to.2);
+        if to.2 == 4 {
+            self.history.push(Ply::Action(Action { from, to, build: None, dome: false }));
+            if let Turn::P1 | Turn::P2 = self.turn {
+                self.win(self.turn, WinReason::Tower);
+            }
+        } else {
+            self.pending = Some((from, to));
+        }
+    }
+    pub fn build_after_move(&mut self, row: usize, column: usize, height: usize, dome: bool) {
+        let Some((from, to)) = self.pending.take() else {
+            panic!("Can't build on ({}, {}, {}) because no worker has moved this turn!", row, column, height);
+        };
+
+        if dome {
+            self.build_dome(row, column, height);
+        } else {
+            self.build(row, column, height);
+        }
+        self.history.push(Ply::Action(Action { from, to, build: Some((row, column, height)), dome }));
+        self.next_turn();
+    }
+    pub fn play(&mut self, action: &Action) {
+        let Action { from, to, build, dome } = *action;
+
+        self.move_worker(from, to);
+        if let Some((row, column, height)) = build {
+            self.build_after_move(row, column, height, dome);
+        }
+    }
+    pub fn apply(&mut self, ply: &Ply) {
+        match *ply {
+            Ply::Action(action) => self.play(&action),
+            Ply::Placement { row, column } => self.place_next_worker(row, column),
+        }
+    }
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+    pub fn get_history(&self) -> &[Ply] {
+        &self.history
+    }
+    pub fn is_placing(&self) -> bool {
+        !self.placements.is_empty()
+    }
+    pub fn place_next_worker(&mut self, row: usize, column: usize) {
+        if self.placements.is_empty() {
+            panic!("Can't place a worker on ({}, {}) because all workers have been placed!", row, column);
+        }
+        let Some(top_height) = self.get_top(row, column) else {
+            panic!("Can't place a worker on ({}, {}) because it's already occupied!", row, column);
+        };
+
+        self.place_worker(row, column, top_height + 1, self.turn);
+        self.history.push(Ply::Placement { row, column });
+        self.placements.remove(0);
+        self.turn = self.placements.first().copied().unwrap_or(self.starter);
+    }
     pub fn place_worker(&mut self, row: usize, column: usize, height: usize, turn: Turn) {
         if self.data[row][column][height].is_some() {
             panic!("Can't place worker on ({}, {}, {}) because it's already occupied!", row, column, height);
@@ -125,6 +466,49 @@ impl Board {
 
         self.data[row][column][height] = Some(Piece::Worker { turn });
     }
+    pub fn remove(&mut self, row: usize, column: usize, height: usize) {
+        if self.data[row][column][height].is_none() {
+            panic!("Can't remove from ({}, {}, {}) because it's empty!", row, column, height);
+        }
+
+        self.data[row][column][height] = None;
+    }
+    pub fn set_domes_anywhere(&mut self, domes_anywhere: bool) {
+        self.domes_anywhere = domes_anywhere;
+    }
+    pub fn set_placements(&mut self, starter: Turn, placements: Vec<Turn>) {
+        self.placements = placements;
+        self.starter = starter;
+    }
+    pub fn set_supply(&mut self, supply: Option<[usize; 4]>) {
+        self.supply = supply;
+    }
+    pub fn set_turn(&mut self, turn: Turn) {
+        self.pending = None;
+        self.result = None;
+        self.turn = turn;
+    }
+    pub fn start_placement(&mut self, starter: Turn, order: PlacementOrder) {
+        let other = starter.opponent();
+        let mut placements = match order {
+            PlacementOrder::Standard => vec![starter, starter, other, other],
+            PlacementOrder::Alternating => vec![starter, other, starter, other],
+            PlacementOrder::StarterLast => vec![other, other, starter, starter],
+        };
+
+        // Workers already on the board don't need to be placed again
+        for PieceMarker { piece, .. } in self.get_pieces() {
+            if let Piece::Worker { turn } = piece {
+                if let Some(i) = placements.iter().position(|x| *x == turn) {
+                    placements.remove(i);
+                }
+            }
+        }
+
+        self.placements = placements;
+        self.starter = starter;
+        self.turn = self.placements.first().copied().unwrap_or(starter);
+    }
     pub fn validate_world_pieces<'a, I>(&self, piece_markers: I) -> bool
         where I: Iterator<Item = &'a PieceMarker>
     {
@@ -141,6 +525,50 @@ impl Board {
 
         true
     }
+    fn find_win(&self) -> Option<(Turn, WinReason)> {
+        if !matches!(self.turn, Turn::P1 | Turn::P2) {
+            return None;
+        }
+
+        let mut p1_exists = false;
+        let mut p1_smothered = true;
+        let mut p2_exists = false;
+        let mut p2_smothered = true;
+        for PieceMarker { piece, row, column, height } in self.get_pieces() {
+            let Piece::Worker { turn } = piece else {
+                continue;
+            };
+            if height == 4 {
+                return Some((turn, WinReason::Tower));
+            }
+
+            let reachable = neighbours(row, column).any(|(row, column)| self.get_top(row, column).is_some_and(|x| x <= height));
+            match turn {
+                Turn::P1 => {
+                    p1_exists = true;
+                    p1_smothered &= !reachable;
+                }
+                Turn::P2 => {
+                    p2_exists = true;
+                    p2_smothered &= !reachable;
+                }
+                _ => unreachable!(),
+            }
+        }
+        if p1_exists && p1_smothered {
+            Some((Turn::P2, WinReason::Blocked))
+        } else if p2_exists && p2_smothered {
+            Some((Turn::P1, WinReason::Blocked))
+        } else {
+            None
+        }
+    }
+    // A game can end between a worker's move and its build, which is then never played
+    fn win(&mut self, winner: Turn, reason: WinReason) {
+        self.pending = None;
+        self.result = Some(GameResult { winner: Some(winner), reason });
+        self.turn = if winner == Turn::P2 { Turn::WinP2 } else { Turn::WinP1 };
+    }
 }
 impl Default for Board {
     fn default() -> Self {
@@ -151,14 +579,56 @@ impl Default for Board {
 
         Self {
             data,
+            domes_anywhere: false,
+            history: Vec::new(),
+            pending: None,
+            placements: Vec::new(),
+            result: None,
+            starter: Turn::default(),
+            supply: None,
             turn: Turn::default(),
         }
     }
 }
 
+// Translucent previews of pieces that could be played, set by whoever is choosing a ply
+#[derive(Default, Resource)]
+pub struct Ghosts {
+    pub pieces: Vec<PieceMarker>,
+}
+
+// A past position shown in place of the live one, whose pieces are hidden meanwhile
+#[derive(Default, Resource)]
+pub struct Preview {
+    pub board: Option<Board>,
+}
+
+#[derive(Clone, Copy, Default, Resource)]
+pub struct Rules {
+    pub domes_anywhere: bool,
+    pub first_player: FirstPlayer,
+    pub limited_supply: bool,
+    pub placement_order: PlacementOrder,
+    pub time_control: TimeControl,
+}
+
+#[derive(Resource)]
+pub struct StartingPosition {
+    pub board: Board,
+}
+
 #[derive(Resource)]
 struct BoardAssets {
     blue_material: Handle<StandardMaterial>,
+    dome_mesh: Handle<Mesh>,
+    ghost_block_material: Handle<StandardMaterial>,
+    ghost_dome_material: Handle<StandardMaterial>,
+    ghost_player1_material: Handle<StandardMaterial>,
+    ghost_player2_material: Handle<StandardMaterial>,
+    last_move_player1_material: Handle<StandardMaterial>,
+    last_move_player2_material: Handle<StandardMaterial>,
+    last_origin_player1_material: Handle<StandardMaterial>,
+    last_origin_player2_material: Handle<StandardMaterial>,
     level1_height: f32,
     level1_mesh: Handle<Mesh>,
     level2_height: f32,
@@ -166,14 +636,34 @@ struct BoardAssets {
     level3_height: f32,
     level3_mesh: Handle<Mesh>,
     level4_height: f32,
-    level4_mesh: Handle<Mesh>,
+    outline_mesh: Handle<Mesh>,
+    player1_height_offset: f32,
     player1_material: Handle<StandardMaterial>,
+    player1_mesh: Handle<Mesh>,
+    player2_height_offset: f32,
     player2_material: Handle<StandardMaterial>,
+    player2_mesh: Handle<Mesh>,
     white_material: Handle<StandardMaterial>,
-    worker_height_offset: f32,
-    worker_mesh: Handle<Mesh>,
 }
 impl BoardAssets {
+    fn get_ghost(&self, piece_marker: &PieceMarker) -> (Transform, Handle<Mesh>, Handle<StandardMaterial>) {
+        let (transform, mesh, _) = self.get_piece(piece_marker);
+        let material = match piece_marker.piece {
+            Piece::Dome => self.ghost_dome_material.clone(),
+            Piece::Worker { turn: Turn::P2 } => self.ghost_player2_material.clone(),
+            Piece::Worker { .. } => self.ghost_player1_material.clone(),
+            _ => self.ghost_block_material.clone(),
+        };
+        (transform, mesh, material)
+    }
+    fn get_last_move_material(&self, turn: Turn, origin: bool) -> Handle<StandardMaterial> {
+        match (turn, origin) {
+            (Turn::P2, true) => self.last_origin_player2_material.clone(),
+            (Turn::P2, false) => self.last_move_player2_material.clone(),
+            (_, true) => self.last_origin_player1_material.clone(),
+            (_, false) => self.last_move_player1_material.clone(),
+        }
+    }
     fn get_piece(&self, piece_marker: &PieceMarker) -> (Transform, Handle<Mesh>, Handle<StandardMaterial>) {
         let PieceMarker {
             piece,
@@ -199,27 +689,37 @@ impl BoardAssets {
                     self.level3_mesh.clone(),
                     self.white_material.clone(),
                 ),
-                4 => (
-                    Transform::from_xyz(row as f32 - 2.0, self.level4_height, column as f32 - 2.0),
-                    self.level4_mesh.clone(),
-                    self.blue_material.clone(),
-                ),
                 _ => panic!("{} is an invalid height!", height),
             },
             Piece::Board => panic!("Can't spawn more board pieces!"),
-            Piece::Worker { turn } => (
+            Piece::Dome => (
                 Transform::from_xyz(
                     row as f32 - 2.0,
                     match height {
-                        1 => self.worker_height_offset + self.level1_height,
-                        2 => self.worker_height_offset + self.level2_height,
-                        3 => self.worker_height_offset + self.level3_height,
-                        4 => self.worker_height_offset + self.level4_height,
+                        1 => self.level1_height,
+                        2 => self.level2_height,
+                        3 => self.level3_height,
+                        4 => self.level4_height,
+                        _ => panic!("{} is an invalid height!", height),
+                    },
+                    column as f32 - 2.0,
+                ),
+                self.dome_mesh.clone(),
+                self.blue_material.clone(),
+            ),
+            Piece::Worker { turn } => (
+                Transform::from_xyz(
+                    row as f32 - 2.0,
+                    if turn == Turn::P2 { self.player2_height_offset } else { self.player1_height_offset } + match height {
+                        1 => self.level1_height,
+                        2 => self.level2_height,
+                        3 => self.level3_height,
+                        4 => self.level4_height,
                         _ => panic!("{} is an invalid height!", height),
                     },
                     column as f32 - 2.0,
                 ),
-                self.worker_mesh.clone(),
+                if turn == Turn::P2 { self.player2_mesh.clone() } else { self.player1_mesh.clone() },
                 self.get_turn_material(turn),
             ),
         }
@@ -228,6 +728,7 @@ impl BoardAssets {
         match turn {
             Turn::P1 | Turn::WinP1 => self.player1_material.clone(),
             Turn::P2 | Turn::WinP2 => self.player2_material.clone(),
+            Turn::Draw => self.white_material.clone(),
         }
     }
 }
@@ -238,15 +739,15 @@ impl BoardAssets {
 struct BaseMarker;
 
 #[derive(Component)]
-struct BoardCamera {
-    pitch: f32,
-    yaw: f32,
-}
-impl Default for BoardCamera {
-    fn default() -> Self {
-        Self { pitch: std::f32::consts::FRAC_PI_4, yaw: 0.0 }
-    }
-}
+struct GhostMarker;
+
+// Outlines the squares of the last ply, as children of the top level on each of them
+#[derive(Component)]
+struct LastMoveMarker;
+
+// Stops clicks from reaching the board, whichever way it's drawn
+#[derive(Component)]
+pub struct PickBlocker;
 
 #[derive(Clone, Component, Copy, Eq, Hash, PartialEq)]
 pub struct PieceMarker {
@@ -256,144 +757,35 @@ pub struct PieceMarker {
     pub height: usize,
 }
 
+#[derive(Component)]
+pub struct PreviewMarker(pub PieceMarker);
+
+#[derive(Component)]
+struct SupplyText;
+
 #[derive(Component, Default)]
 struct TurnIndicatorMarker {
     turn: Turn,
 }
 
-#[derive(Component)]
-struct WinText;
-
 // Systems
 
-fn camera_input(
-    mut camera_query: Query<&mut BoardCamera>,
-    mut mouse_evr: EventReader<MouseMotion>,
-    mouse: Res<Input<MouseButton>>,
-    window_query: Query<&Window>,
-) {
-    const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.001;
-    const SENSITIVITY: Vec2 = Vec2::new(60.0, 30.0);
-
-    if !mouse.pressed(MouseButton::Right) {
-        return;
-    }
-
-    let window = window_query.single();
-
-    let mut rotation = Vec2::ZERO;
-    for ev in mouse_evr.read() {
-        rotation = ev.delta / Vec2::new(window.width(), window.height()) * SENSITIVITY;
-    }
-
-    let mut camera = camera_query.single_mut();
-    camera.pitch = (camera.pitch + rotation.y).clamp(0.0, MAX_PITCH);
-    camera.yaw = camera.yaw + rotation.x;
-    while camera.yaw < 0.0 {
-        camera.yaw += std::f32::consts::TAU;
-    }
-    while camera.yaw > std::f32::consts::TAU {
-        camera.yaw -= std::f32::consts::TAU;
-    }
-}
-
 fn check_win(
     mut board: ResMut<Board>,
 ) {
-    const IS_NEIGHBOUR_REACHABLE: fn(usize, usize, usize, &mut ResMut<Board>) -> bool =
-    |row, column, height, board| {
-        if row > 0 && column > 0 {
-            if IS_REACHABLE(row - 1, column - 1, height, board) {
-                return true;
-            }
-        }
-        if row > 0 {
-            if IS_REACHABLE(row - 1, column, height, board) {
-                return true;
-            }
-        }
-        if row > 0 && column < 4 {
-            if IS_REACHABLE(row - 1, column + 1, height, board) {
-                return true;
-            }
-        }
-        if column > 0 {
-            if IS_REACHABLE(row, column - 1, height, board) {
-                return true;
-            }
-        }
-        if column < 4 {
-            if IS_REACHABLE(row, column + 1, height, board) {
-                return true;
-            }
-        }
-        if row < 4 && column > 0 {
-            if IS_REACHABLE(row + 1, column - 1, height, board) {
-                return true;
-            }
-        }
-        if row < 4 {
-            if IS_REACHABLE(row + 1, column, height, board) {
-                return true;
-            }
-        }
-        if row < 4 && column < 4 {
-            if IS_REACHABLE(row + 1, column + 1, height, board) {
-                return true;
-            }
-        }
-        false
-    };
-    const IS_REACHABLE: fn(usize, usize, usize, &mut ResMut<Board>) -> bool =
-    |row, column, height, board| {
-        if let Some(top_height) = board.get_top(row, column) {
-            top_height <= height
-        } else {
-            false
-        }
-    };
-
-    let mut p1_exists = false;
-    let mut p1_smothered = true;
-    let mut p2_exists = false;
-    let mut p2_smothered = true;
-    for PieceMarker { piece, row, column, height } in board.get_pieces() {
-        if let Piece::Worker { turn } = piece {
-            match turn {
-                Turn::P1 => {
-                    if height == 4 {
-                        board.turn = Turn::WinP1;
-                        return;
-                    }
-                    p1_exists = true;
-                    if IS_NEIGHBOUR_REACHABLE(row, column, height, &mut board) {
-                        p1_smothered = false;
-                    }
-                }
-                Turn::P2 => {
-                    if height == 4 {
-                        board.turn = Turn::WinP2;
-                        return;
-                    }
-                    p2_exists = true;
-                    if IS_NEIGHBOUR_REACHABLE(row, column, height, &mut board) {
-                        p2_smothered = false;
-                    }
-                }
-                _ => unreachable!(),
-            }
-        }
-    }
-    if p1_exists && p1_smothered {
-        board.turn = Turn::WinP2;
-    } else if p2_exists && p2_smothered {
-        board.turn = Turn::WinP1;
+    // The board is only borrowed mutably once there's a winner, to keep change detection quiet
+    if let Some((winner, reason)) = board.find_win() {
+        board.win(winner, reason);
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn cleanup(
     mut commands: Commands,
-    query: Query<Entity, Or<(With<BoardCamera>, With<BaseMarker>, With<PieceMarker>, With<WinText>)>>,
+    query: Query<Entity, Or<(
+        With<BoardCamera>, With<BaseMarker>, With<GhostMarker>, With<LastMoveMarker>, With<PieceMarker>, With<PreviewMarker>,
+        With<SupplyText>,
+    )>>,
 ) {
     for entity in query.iter() {
         commands.entity(entity).despawn();
@@ -402,73 +794,125 @@ fn cleanup(
     commands.remove_resource::<AmbientLight>();
     commands.remove_resource::<Board>();
     commands.remove_resource::<BoardAssets>();
+    commands.remove_resource::<Ghosts>();
+    commands.remove_resource::<Preview>();
 }
 
 fn setup(
     mut commands: Commands,
+    asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
+    rules: Res<Rules>,
+    starting_position: Option<Res<StartingPosition>>,
+    theme: Res<Theme>,
 ) {
-    // Recurring assets
-    let board_assets = BoardAssets {
-        blue_material: materials.add(Color::BLUE.into()),
-        level1_height: 0.0,
-        level1_mesh: meshes.add(shape::Box {
-            min_x: -0.475,
-            max_x: 0.475,
-            min_y: 0.0,
-            max_y: 1.0,
-            min_z: -0.475,
-            max_z: 0.475,
-        }.into()),
-        level2_height: 1.0,
-        level2_mesh: meshes.add(shape::Box {
-            min_x: -0.425,
-            max_x: 0.425,
-            min_y: 0.0,
-            max_y: 0.8,
-            min_z: -0.425,
-            max_z: 0.425,
-        }.into()),
-        level3_height: 1.8,
-        level3_mesh: meshes.add(shape::Box {
-            min_x: -0.4,
-            max_x: 0.4,
-            min_y: 0.0,
-            max_y: 0.6,
-            min_z: -0.4,
-            max_z: 0.4,
-        }.into()),
-        level4_height: 2.4,
-        level4_mesh: meshes.add(shape::Box {
-            min_x: -0.4,
-            max_x: 0.4,
-            min_y: 0.0,
-            max_y: 0.25,
-            min_z: -0.4,
-            max_z: 0.4,
-        }.into()),
-        white_material: materials.add(Color::rgb_u8(250, 254, 255).into()),
-        player1_material: materials.add(StandardMaterial {
-            base_color: Color::GOLD,
+    let palette = theme.palette();
+    let worker_material = |color: Color| if palette.metallic {
+        StandardMaterial {
+            base_color: color,
             metallic: 1.0,
             reflectance: 0.8,
             perceptual_roughness: 0.4,
             ..default()
-        }),
-        player2_material: materials.add(StandardMaterial {
-            base_color: Color::SILVER,
-            metallic: 1.0,
-            reflectance: 0.8,
-            perceptual_roughness: 0.4,
-            ..default()
-        }),
-        worker_height_offset: 0.4,
-        worker_mesh: meshes.add(shape::Capsule {
+        }
+    } else {
+        color.into()
+    };
+    let ghost_material = |color: Color| StandardMaterial {
+        base_color: color,
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    };
+    let outline_material = |color: Color| StandardMaterial {
+        base_color: color,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    };
+
+    let mut model_or = |name: &str, fallback: Mesh| {
+        load_model(&asset_server, name).unwrap_or_else(|| meshes.add(fallback))
+    };
+
+    // Recurring assets
+    let dome_mesh = model_or("dome.glb", shape::Box {
+        min_x: -0.4,
+        max_x: 0.4,
+        min_y: 0.0,
+        max_y: 0.25,
+        min_z: -0.4,
+        max_z: 0.4,
+    }.into());
+    let level1_mesh = model_or("level1.glb", shape::Box {
+        min_x: -0.475,
+        max_x: 0.475,
+        min_y: 0.0,
+        max_y: 1.0,
+        min_z: -0.475,
+        max_z: 0.475,
+    }.into());
+    let level2_mesh = model_or("level2.glb", shape::Box {
+        min_x: -0.425,
+        max_x: 0.425,
+        min_y: 0.0,
+        max_y: 0.8,
+        min_z: -0.425,
+        max_z: 0.425,
+    }.into());
+    let level3_mesh = model_or("level3.glb", shape::Box {
+        min_x: -0.4,
+        max_x: 0.4,
+        min_y: 0.0,
+        max_y: 0.6,
+        min_z: -0.4,
+        max_z: 0.4,
+    }.into());
+    // The players' workers differ in shape too, for when their colours are hard to tell apart. The
+    // primitives are centred on their origin, so they are raised by half their height
+    let (player1_mesh, player1_height_offset) = match load_model(&asset_server, "gold_worker.glb") {
+        Some(mesh) => (mesh, 0.0),
+        None => (meshes.add(shape::Capsule {
             radius: 0.2,
             depth: 0.4,
             ..default()
-        }.into()),
+        }.into()), 0.4),
+    };
+    let (player2_mesh, player2_height_offset) = match load_model(&asset_server, "silver_worker.glb") {
+        Some(mesh) => (mesh, 0.0),
+        None => (meshes.add(shape::Cylinder {
+            radius: 0.2,
+            height: 0.8,
+            ..default()
+        }.into()), 0.4),
+    };
+
+    let board_assets = BoardAssets {
+        blue_material: materials.add(palette.dome.into()),
+        dome_mesh,
+        ghost_block_material: materials.add(ghost_material(palette.block.with_a(0.4))),
+        ghost_dome_material: materials.add(ghost_material(palette.dome.with_a(0.5))),
+        ghost_player1_material: materials.add(ghost_material(palette.player1.with_a(0.4))),
+        ghost_player2_material: materials.add(ghost_material(palette.player2.with_a(0.4))),
+        last_move_player1_material: materials.add(outline_material(palette.player1.with_a(0.85))),
+        last_move_player2_material: materials.add(outline_material(palette.player2.with_a(0.85))),
+        last_origin_player1_material: materials.add(outline_material(palette.player1.with_a(0.4))),
+        last_origin_player2_material: materials.add(outline_material(palette.player2.with_a(0.4))),
+        level1_height: 0.0,
+        level1_mesh,
+        level2_height: 1.0,
+        level2_mesh,
+        level3_height: 1.8,
+        level3_mesh,
+        level4_height: 2.4,
+        outline_mesh: meshes.add(shape::Cube { size: 1.0 }.into()),
+        white_material: materials.add(palette.block.into()),
+        player1_height_offset,
+        player1_material: materials.add(worker_material(palette.player1)),
+        player1_mesh,
+        player2_height_offset,
+        player2_material: materials.add(worker_material(palette.player2)),
+        player2_mesh,
     };
 
     // Camera
@@ -505,8 +949,7 @@ fn setup(
     ));
     commands.spawn((
         PbrBundle {
-            mesh: meshes.add(shape::Box::from_corners(
-                Vec3::new(-2.9, -0.21, -2.9), Vec3::new(2.9, -0.49, 2.9)).into()),
+            mesh: meshes.add(shape::Box::from_corners(TURN_INDICATOR.0, TURN_INDICATOR.1).into()),
             material: board_assets.player1_material.clone(),
             ..default()
         },
@@ -516,9 +959,9 @@ fn setup(
 
     // Level 0 board
     let light_square_material = 
-        materials.add(Color::rgb_u8(117, 205, 255).into());
+        materials.add(palette.light_square.into());
     let dark_square_material = 
-        materials.add(Color::rgb_u8(65, 92, 224).into());
+        materials.add(palette.dark_square.into());
     let square_mesh =
         meshes.add(shape::Box::from_corners(Vec3::new(-0.5, 0.0, -0.5), Vec3::new(0.5, 0.05, 0.5)).into());
     for (i, j) in (-2..=2).cartesian_product(-2..=2) {
@@ -538,83 +981,107 @@ fn setup(
         ));
     }
 
+    let board = match starting_position {
+        Some(starting_position) => starting_position.board.clone(),
+        None => Board::new(&rules),
+    };
+
+    // Supply HUD
+    if board.supply.is_some() {
+        commands.spawn((
+            TextBundle {
+                text: Text::from_section("", TextStyle {
+                    color: Color::WHITE,
+                    font_size: 20.0,
+                    ..default()
+                }),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(5.0),
+                    bottom: Val::Px(5.0),
+                    ..default()
+                },
+                ..default()
+            },
+            SupplyText,
+        ));
+    }
+
+    // Pieces already on the board don't animate in
+    for piece_marker in board.get_pieces() {
+        let (transform, mesh, material) = board_assets.get_piece(&piece_marker);
+        commands.spawn((
+            PbrBundle {
+                transform,
+                mesh,
+                material,
+                ..default()
+            },
+            piece_marker,
+        ));
+    }
+
     // Inserts resources
-    commands.insert_resource(Board::default());
+    commands.insert_resource(board);
     commands.insert_resource(board_assets);
+    commands.init_resource::<Ghosts>();
+    commands.init_resource::<Preview>();
 }
 
 fn update_board(
     mut commands: Commands,
     mut turn_indicator_query: Query<(Entity, &mut TurnIndicatorMarker)>,
+    animation_speed: Res<AnimationSpeed>,
     board: Res<Board>,
     board_assets: Res<BoardAssets>,
-    pieces_query: Query<(Entity, &PieceMarker)>,
-    win_text_query: Query<(), With<WinText>>,   
-    
+    pieces_query: Query<(Entity, &PieceMarker, &Transform)>,
+    preview: Res<Preview>,
 ) {
-    match board.get_turn() {
-        Turn::WinP1 => if win_text_query.is_empty() {
-            commands.spawn((
-                TextBundle {
-                    text: Text::from_section("Gold wins!", TextStyle {
-                        color: Color::GREEN,
-                        font_size: 24.0,
-                        ..default()
-                    }),
-                    style: Style {
-                        position_type: PositionType::Absolute,
-                        right: Val::Px(5.0),
-                        top: Val::Px(5.0),
-                        ..default()
-                    },
-                    ..default()
-                },
-                WinText,
-            ));
-        },
-        Turn::WinP2 => if win_text_query.is_empty() {
-            commands.spawn((
-                TextBundle {
-                    text: Text::from_section("Silver wins!", TextStyle {
-                        color: Color::GREEN,
-                        font_size: 24.0,
-                        ..default()
-                    }),
-                    style: Style {
-                        position_type: PositionType::Absolute,
-                        right: Val::Px(5.0),
-                        top: Val::Px(5.0),
-                        ..default()
-                    },
-                    ..default()
-                },
-                WinText,
-            ));
-        },
-        _ => {}
-    }
-
     let mut board_pieces = board.get_pieces();
+    let visibility = if preview.board.is_some() { Visibility::Hidden } else { Visibility::Inherited };
 
-    for (entity, piece_marker) in pieces_query.iter() {
-        if !board_pieces.remove(piece_marker) {
-            if piece_marker.height > 0 {
-                commands.entity(entity).despawn();
+    // Workers that left a square hop from wherever they were shown to their new one
+    let mut moved_workers = Vec::new();
+    for (entity, piece_marker, transform) in pieces_query.iter() {
+        if !board_pieces.remove(piece_marker) && piece_marker.height > 0 {
+            if let Piece::Worker { turn } = piece_marker.piece {
+                moved_workers.push((turn, transform.translation));
             }
+            commands.entity(entity).despawn();
         }
     }
 
     for piece_marker in board_pieces.into_iter() {
         let (transform, mesh, material) = board_assets.get_piece(&piece_marker);
-        commands.spawn((
+        let to = transform.translation;
+        let tween = match piece_marker.piece {
+            Piece::Worker { turn } => {
+                let closest = moved_workers
+                    .iter()
+                    .positions(|(x, _)| *x == turn)
+                    .min_by(|x, y| moved_workers[*x].1.distance(to).total_cmp(&moved_workers[*y].1.distance(to)));
+                match closest {
+                    Some(i) => Tween::new(TweenKind::Hop, moved_workers.swap_remove(i).1, to, *animation_speed),
+                    None => Tween::fall(TweenKind::Drop, to, *animation_speed),
+                }
+            }
+            Piece::Dome => Tween::fall(TweenKind::Settle, to, *animation_speed),
+            _ => Tween::fall(TweenKind::Drop, to, *animation_speed),
+        };
+
+        let mut entity = commands.spawn((
             PbrBundle {
-                transform,
+                transform: Transform::from_translation(tween.as_ref().map_or(to, |x| x.translation())),
                 mesh,
                 material,
+                visibility,
                 ..default()
             },
             piece_marker,
         ));
+        if let Some(tween) = tween {
+            entity.insert(tween);
+        }
     }
 
     let (entity, mut turn_indicator_marker) = turn_indicator_query.single_mut();
@@ -624,18 +1091,331 @@ fn update_board(
     }
 }
 
-fn update_camera(
-    mut camera_query: Query<(&mut Transform, &BoardCamera)>,
+fn update_ghosts(
+    mut commands: Commands,
+    board_assets: Res<BoardAssets>,
+    ghost_query: Query<Entity, With<GhostMarker>>,
+    ghosts: Res<Ghosts>,
+    preview: Res<Preview>,
+) {
+    if !ghosts.is_changed() {
+        return;
+    }
+
+    for entity in ghost_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    // Ghosts can be seen through and clicked through
+    for piece_marker in ghosts.pieces.iter() {
+        let (transform, mesh, material) = board_assets.get_ghost(piece_marker);
+        commands.spawn((
+            PbrBundle {
+                transform,
+                mesh,
+                material,
+                visibility: if preview.board.is_some() { Visibility::Hidden } else { Visibility::Inherited },
+                ..default()
+            },
+            GhostMarker,
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+fn update_last_move(
+    mut commands: Commands,
+    mut shown: Local<usize>,
+    board: Res<Board>,
+    board_assets: Res<BoardAssets>,
+    outline_query: Query<(Entity, &Parent), With<LastMoveMarker>>,
+    pieces_query: Query<(Entity, &PieceMarker)>,
+    preview: Res<Preview>,
+) {
+    // Building where the worker came from outlines the square as a build. Previews go without
+    let mut squares: HashMap<(usize, usize), bool> = HashMap::new();
+    let last = if preview.board.is_none() { board.get_history().last() } else { None };
+    match last {
+        Some(Ply::Action(Action { from, to, build, dome: _ })) => {
+            squares.insert((from.0, from.1), true);
+            squares.insert((to.0, to.1), false);
+            if let Some(build) = build {
+                squares.insert((build.0, build.1), false);
+            }
+        }
+        Some(Ply::Placement { row, column }) => {
+            squares.insert((*row, *column), false);
+        }
+        None => {}
+    }
+
+    // Outlines sit on the top level so that they rise with the towers, following new levels as they fall
+    let targets: Vec<(Entity, usize, bool)> = squares
+        .iter()
+        .filter_map(|(square, origin)| {
+            pieces_query
+                .iter()
+                .filter(|(_, x)| (x.row, x.column) == *square && matches!(x.piece, Piece::Block | Piece::Board))
+                .max_by_key(|(_, x)| x.height)
+                .map(|(entity, x)| (entity, x.height, *origin))
+        })
+        .collect();
+
+    let outlined: HashSet<Entity> = outline_query.iter().map(|(_, x)| x.get()).collect();
+    if *shown == board.get_history().len() && outlined == targets.iter().map(|x| x.0).collect() {
+        return;
+    }
+    *shown = board.get_history().len();
+
+    for (entity, _) in outline_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // The worker that played stands on the square it moved to or was placed on
+    let Some(turn) = squares
+        .iter()
+        .find(|(_, origin)| !**origin)
+        .and_then(|((row, column), _)| match board.get_piece(*row, *column, board.get_levels(*row, *column) + 1) {
+            Some(Piece::Worker { turn }) => Some(*turn),
+            _ => None,
+        })
+    else {
+        return;
+    };
+
+    const THICKNESS: f32 = 0.06;
+    for (entity, height, origin) in targets {
+        let (top, width) = TOP_FACES[height];
+        let width = width + 0.1;
+        let offset = (width - THICKNESS) / 2.0;
+        let material = board_assets.get_last_move_material(turn, origin);
+        commands.entity(entity).with_children(|parent| {
+            for (position, scale) in [
+                (Vec3::new(offset, 0.0, 0.0), Vec3::new(THICKNESS, 0.02, width)),
+                (Vec3::new(-offset, 0.0, 0.0), Vec3::new(THICKNESS, 0.02, width)),
+                (Vec3::new(0.0, 0.0, offset), Vec3::new(width, 0.02, THICKNESS)),
+                (Vec3::new(0.0, 0.0, -offset), Vec3::new(width, 0.02, THICKNESS)),
+            ] {
+                parent.spawn((
+                    PbrBundle {
+                        mesh: board_assets.outline_mesh.clone(),
+                        material: material.clone(),
+                        transform: Transform::from_translation(position + Vec3::Y * (top + 0.01)).with_scale(scale),
+                        ..default()
+                    },
+                    LastMoveMarker,
+                    NotShadowCaster,
+                    Pickable::IGNORE,
+                ));
+            }
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_preview(
+    mut commands: Commands,
+    mut pieces_query: Query<(&PieceMarker, &mut Visibility), Without<PreviewMarker>>,
+    mut ghost_query: Query<&mut Visibility, (With<GhostMarker>, Without<PieceMarker>, Without<PreviewMarker>)>,
+    board_assets: Res<BoardAssets>,
+    preview: Res<Preview>,
+    preview_query: Query<Entity, With<PreviewMarker>>,
+) {
+    if !preview.is_changed() {
+        return;
+    }
+
+    for entity in preview_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    // The level 0 squares are shared by both, everything on them is swapped
+    let target = if preview.board.is_some() { Visibility::Hidden } else { Visibility::Inherited };
+    for (_, mut visibility) in pieces_query.iter_mut().filter(|(x, _)| x.height > 0) {
+        *visibility = target;
+    }
+    for mut visibility in ghost_query.iter_mut() {
+        *visibility = target;
+    }
+
+    let Some(board) = preview.board.as_ref() else {
+        return;
+    };
+    for piece_marker in board.get_pieces() {
+        let (transform, mesh, material) = board_assets.get_piece(&piece_marker);
+        commands.spawn((
+            PbrBundle {
+                transform,
+                mesh,
+                material,
+                ..default()
+            },
+            Pickable::IGNORE,
+            PreviewMarker(piece_marker),
+        ));
+    }
+}
+
+fn update_supply_text(
+    mut supply_text_query: Query<&mut Text, With<SupplyText>>,
+    board: Res<Board>,
 ) {
-    const DISTANCE: f32 = 10.0;
+    if !board.is_changed() {
+        return;
+    }
+
+    for mut text in supply_text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "Level 1: {}\nLevel 2: {}\nLevel 3: {}\nDomes: {}",
+            board.get_supply(1).unwrap_or_default(),
+            board.get_supply(2).unwrap_or_default(),
+            board.get_supply(3).unwrap_or_default(),
+            board.get_supply(4).unwrap_or_default(),
+        );
+    }
+}
+
+// Functions
 
-    let (mut tranform, camera) = camera_query.single_mut();
+// Models replace the primitive pieces when they are found in the models directory:
+//
+// level1.glb, level2.glb, level3.glb, dome.glb, gold_worker.glb and silver_worker.glb
+//
+// Only the first primitive of the first mesh in each file is used, and it's coloured by the theme
+// rather than by its own materials. Pieces stand on their origin, with the squares one unit across
+// and the levels 1.0, 0.8 and 0.6 tall
+fn load_model(asset_server: &AssetServer, name: &str) -> Option<Handle<Mesh>> {
+    let path = FileAssetReader::get_base_path().join(MODELS_DIRECTORY).join(name);
+    path.is_file().then(|| asset_server.load(format!("models/{}#Mesh0/Primitive0", name)))
+}
 
-    let (pitch_sin, pitch_cos) = camera.pitch.sin_cos();
-    let (yaw_sin, yaw_cos) = camera.yaw.sin_cos();
-    *tranform = Transform::from_xyz(
-        DISTANCE * yaw_cos * pitch_cos,
-        DISTANCE * pitch_sin,
-        DISTANCE * yaw_sin * pitch_cos,
-    ).looking_at(Vec3::ZERO, Vec3::Y);
+pub fn neighbours(row: usize, column: usize) -> impl Iterator<Item = (usize, usize)> {
+    (row.saturating_sub(1)..=(row + 1).min(4))
+        .cartesian_product(column.saturating_sub(1)..=(column + 1).min(4))
+        .filter(move |x| *x != (row, column))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resigned_position_can_be_played_on() {
+        let mut board = Board::new(&Rules::default());
+        for (row, column) in [(1, 1), (1, 3), (3, 1), (3, 3)] {
+            board.place_next_worker(row, column);
+        }
+
+        // Resigning between the move and the build
+        let Action { from, to, .. } = board.get_actions()[0];
+        board.move_worker(from, to);
+        board.forfeit(WinReason::Resignation);
+
+        // Analyzing continues the finished position with the loser to move
+        let mut position = board.analysis();
+        let turn = *position.get_turn();
+        assert_eq!(Some(turn), board.get_result().and_then(|x| x.winner).map(|x| x.opponent()));
+
+        let action = position.get_actions()[0];
+        position.play(&action);
+        assert_eq!(*position.get_turn(), turn.opponent());
+        assert_eq!(position.get_history().len(), 1);
+    }
+
+    #[test]
+    fn analyzed_position_can_be_built_on_without_supply() {
+        let mut board = Board::new(&Rules { limited_supply: true, ..default() });
+        for (row, column) in [(0, 0), (0, 4), (4, 0), (4, 4)] {
+            board.place_next_worker(row, column);
+        }
+        board.set_supply(Some([0; 4]));
+        board.forfeit(WinReason::Resignation);
+
+        // The editor raises and domes squares whatever was left in the game's supply
+        let mut position = board.analysis();
+        position.build(2, 2, 1);
+        position.build_dome(2, 2, 2);
+        assert_eq!(position.get_supply(1), None);
+        assert!(position.get_placements().is_empty());
+    }
+    #[test]
+    fn limited_supply_runs_out_as_pieces_are_built() {
+        assert_eq!(Board::new(&Rules::default()).get_supply(1), None);
+
+        let mut board = Board::new(&Rules { limited_supply: true, ..default() });
+        for height in 1..=4 {
+            assert_eq!(board.get_supply(height), Some(PIECE_SUPPLY[height - 1]));
+        }
+
+        board.build(2, 2, 1);
+        board.build(2, 2, 2);
+        board.build_dome(2, 2, 3);
+        assert_eq!(board.get_supply(1), Some(PIECE_SUPPLY[0] - 1));
+        assert_eq!(board.get_supply(2), Some(PIECE_SUPPLY[1] - 1));
+        assert_eq!(board.get_supply(3), Some(PIECE_SUPPLY[2]));
+        assert_eq!(board.get_supply(4), Some(PIECE_SUPPLY[3] - 1));
+    }
+
+    #[test]
+    fn supply_leaves_out_pieces_already_on_the_board() {
+        let mut board = Board::default();
+        board.build(0, 0, 1);
+        board.build(0, 0, 2);
+        board.build(1, 1, 1);
+        board.build_dome(1, 1, 2);
+
+        board.apply_rules(Turn::P1, &Rules { limited_supply: true, ..default() });
+        assert_eq!(board.get_supply(1), Some(PIECE_SUPPLY[0] - 2));
+        assert_eq!(board.get_supply(2), Some(PIECE_SUPPLY[1] - 1));
+        assert_eq!(board.get_supply(3), Some(PIECE_SUPPLY[2]));
+        assert_eq!(board.get_supply(4), Some(PIECE_SUPPLY[3] - 1));
+    }
+
+    #[test]
+    fn exhausted_levels_cant_be_built() {
+        let mut board = Board::default();
+        board.build(0, 0, 1);
+        board.build(0, 0, 2);
+        board.build(1, 1, 1);
+        board.build(1, 1, 2);
+        board.build(1, 1, 3);
+        board.set_supply(Some([1, 1, 0, 0]));
+
+        assert!(board.can_build_block(2, 2));
+        assert!(!board.can_build_block(0, 0));
+        assert!(!board.can_build_dome(1, 1));
+        assert!(!board.can_build(1, 1));
+
+        board.set_supply(Some([1, 1, 1, 1]));
+        assert!(board.can_build_block(0, 0));
+        assert!(board.can_build_dome(1, 1));
+    }
+    #[test]
+    fn placement_order_decides_who_places_each_worker() {
+        let (gold, silver) = (Turn::P1, Turn::P2);
+        for (order, expected) in [
+            (PlacementOrder::Standard, [silver, silver, gold, gold]),
+            (PlacementOrder::Alternating, [silver, gold, silver, gold]),
+            (PlacementOrder::StarterLast, [gold, gold, silver, silver]),
+        ] {
+            let mut board = Board::default();
+            board.start_placement(silver, order);
+            assert_eq!(board.get_placements(), expected);
+
+            let mut placed = Vec::new();
+            for column in 0..4 {
+                placed.push(*board.get_turn());
+                board.place_next_worker(0, column);
+            }
+            assert_eq!(placed, expected);
+
+            // The starter moves first once every worker is placed
+            assert!(!board.is_placing());
+            assert_eq!(*board.get_turn(), silver);
+        }
+
+        // Workers already on the board are left out
+        let mut board = Board::default();
+        board.place_worker(2, 2, 1, Turn::P2);
+        board.start_placement(gold, PlacementOrder::Alternating);
+        assert_eq!(board.get_placements(), [gold, gold, silver]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
+use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4, PI, TAU};
+
+use crate::{
+    AppState,
+    board::{Board, Turn},
+    network::ChatInput,
+};
+
+pub struct CameraPlugin;
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<CameraSettings>()
+            .add_systems(PreUpdate,
+                camera_input.run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor)))
+            )
+            .add_systems(Update,
+                (
+                    follow_turn,
+                    update_camera,
+                ).chain().run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor)))
+            );
+    }
+}
+
+// Constants
+
+const DEFAULT_DISTANCE: f32 = 10.0;
+const MAX_DISTANCE: f32 = 16.0;
+const MAX_FOCUS: f32 = 2.5;
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.001;
+const MIN_DISTANCE: f32 = 5.0;
+const ORBIT_SPEED: f32 = 1.5;
+const PAN_SENSITIVITY: f32 = 1.0;
+const ROTATE_SENSITIVITY: Vec2 = Vec2::new(60.0, 30.0);
+const SMOOTHING: f32 = 12.0;
+const ZOOM_STEP: f32 = 0.9;
+
+// Structs
+
+#[derive(Clone, Copy)]
+struct CameraView {
+    distance: f32,
+    focus: Vec3,
+    pitch: f32,
+    yaw: f32,
+}
+impl Default for CameraView {
+    fn default() -> Self {
+        Self { distance: DEFAULT_DISTANCE, focus: Vec3::ZERO, pitch: FRAC_PI_4, yaw: 0.0 }
+    }
+}
+
+// Resources
+
+#[derive(Default, Resource)]
+pub struct CameraSettings {
+    // Turns the camera towards the side to move whenever the turn changes
+    pub follow_turn: bool,
+}
+
+// Components
+
+#[derive(Component, Default)]
+pub struct BoardCamera {
+    followed: Option<Turn>,
+    // Input moves the target, the view eases towards it
+    target: CameraView,
+    view: CameraView,
+}
+
+// Systems
+
+#[allow(clippy::too_many_arguments)]
+fn camera_input(
+    mut camera_query: Query<&mut BoardCamera>,
+    mut mouse_evr: EventReader<MouseMotion>,
+    mut settings: ResMut<CameraSettings>,
+    mut wheel_evr: EventReader<MouseWheel>,
+    chat_input: Option<Res<ChatInput>>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    time: Res<Time>,
+    window_query: Query<&Window>,
+) {
+    let window = window_query.single();
+    let mut camera = camera_query.single_mut();
+    let target = &mut camera.target;
+
+    // Right-drag orbits, middle-drag slides the board around
+    let delta: Vec2 = mouse_evr.read().map(|ev| ev.delta).sum();
+    if mouse.pressed(MouseButton::Right) {
+        let rotation = delta / Vec2::new(window.width(), window.height()) * ROTATE_SENSITIVITY;
+        target.pitch += rotation.y;
+        target.yaw += rotation.x;
+    } else if mouse.pressed(MouseButton::Middle) {
+        let (yaw_sin, yaw_cos) = target.yaw.sin_cos();
+        let forward = Vec3::new(-yaw_cos, 0.0, -yaw_sin);
+        let right = Vec3::new(yaw_sin, 0.0, -yaw_cos);
+        let pan = delta / window.height() * target.distance * PAN_SENSITIVITY;
+        target.focus = (target.focus - right * pan.x + forward * pan.y)
+            .clamp(Vec3::new(-MAX_FOCUS, 0.0, -MAX_FOCUS), Vec3::new(MAX_FOCUS, 0.0, MAX_FOCUS));
+    }
+
+    for ev in wheel_evr.read() {
+        // Touchpads scroll in pixels rather than lines
+        let lines = match ev.unit {
+            MouseScrollUnit::Line => ev.y,
+            MouseScrollUnit::Pixel => ev.y / 100.0,
+        };
+        target.distance = (target.distance * ZOOM_STEP.powf(lines)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    // The keyboard belongs to the chat line while a message is being typed
+    if !chat_input.is_some_and(|x| x.is_typing()) {
+        let orbit = ORBIT_SPEED * time.delta_seconds();
+        if keys.pressed(KeyCode::Left) {
+            target.yaw -= orbit;
+        }
+        if keys.pressed(KeyCode::Right) {
+            target.yaw += orbit;
+        }
+        if keys.pressed(KeyCode::Up) {
+            target.pitch += orbit;
+        }
+        if keys.pressed(KeyCode::Down) {
+            target.pitch -= orbit;
+        }
+
+        let preset = if keys.just_pressed(KeyCode::Key1) {
+            Some((MAX_PITCH, target.yaw))
+        } else if keys.just_pressed(KeyCode::Key2) {
+            Some((FRAC_PI_4, side_yaw(Turn::P1)))
+        } else if keys.just_pressed(KeyCode::Key3) {
+            Some((FRAC_PI_4, side_yaw(Turn::P2)))
+        } else if keys.just_pressed(KeyCode::Key4) {
+            Some((FRAC_1_SQRT_2.atan(), side_yaw(Turn::P1) + FRAC_PI_4))
+        } else {
+            None
+        };
+        if let Some((pitch, yaw)) = preset {
+            *target = CameraView { pitch, yaw: nearest_angle(target.yaw, yaw), ..default() };
+        }
+
+        if keys.just_pressed(KeyCode::F) {
+            settings.follow_turn = !settings.follow_turn;
+            camera.followed = None;
+        }
+    }
+
+    camera.target.pitch = camera.target.pitch.clamp(0.0, MAX_PITCH);
+}
+
+fn follow_turn(
+    mut camera_query: Query<&mut BoardCamera>,
+    board: Res<Board>,
+    settings: Res<CameraSettings>,
+) {
+    let turn = *board.get_turn();
+    let mut camera = camera_query.single_mut();
+    if !settings.follow_turn || !matches!(turn, Turn::P1 | Turn::P2) || camera.followed == Some(turn) {
+        return;
+    }
+
+    camera.followed = Some(turn);
+    camera.target.yaw = nearest_angle(camera.target.yaw, side_yaw(turn));
+}
+
+fn update_camera(
+    mut camera_query: Query<(&mut Transform, &mut BoardCamera)>,
+    time: Res<Time>,
+) {
+    let (mut transform, mut camera) = camera_query.single_mut();
+    let BoardCamera { target, view, .. } = &mut *camera;
+
+    // Closes the same share of the gap every second whatever the frame rate
+    let t = 1.0 - (-SMOOTHING * time.delta_seconds()).exp();
+    view.distance += (target.distance - view.distance) * t;
+    view.focus = view.focus.lerp(target.focus, t);
+    view.pitch += (target.pitch - view.pitch) * t;
+    view.yaw += (target.yaw - view.yaw) * t;
+
+    let (pitch_sin, pitch_cos) = view.pitch.sin_cos();
+    let (yaw_sin, yaw_cos) = view.yaw.sin_cos();
+    let offset = view.distance * Vec3::new(yaw_cos * pitch_cos, pitch_sin, yaw_sin * pitch_cos);
+    *transform = Transform::from_translation(view.focus + offset).looking_at(view.focus, Vec3::Y);
+}
+
+// Functions
+
+// The angle equivalent to `to` that is the shortest turn away from `from`
+fn nearest_angle(from: f32, to: f32) -> f32 {
+    from + (to - from + PI).rem_euclid(TAU) - PI
+}
+
+fn side_yaw(turn: Turn) -> f32 {
+    if turn == Turn::P2 { PI } else { 0.0 }
+}
\ No newline at end of file
@@ -0,0 +1,323 @@
+use bevy::prelude::*;
+
+use bevy::ui::UiSystem;
+use std::time::Duration;
+
+use crate::{
+    AppState,
+    board::{Board, Rules, TURN_INDICATOR, TimeControl, Turn, WinReason},
+    camera::BoardCamera,
+    controller::{Controller, Controllers},
+    menus::Paused,
+    network::NetworkSession,
+    puzzle::Puzzle,
+    theme::Theme,
+};
+
+pub struct ClockPlugin;
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(Update, (
+                tick_clocks,
+                update_clock_texts,
+            ).chain().run_if(in_state(AppState::InGame).and_then(resource_exists::<Clocks>())))
+            .add_systems(PostUpdate,
+                place_clocks.before(UiSystem::Layout).run_if(in_state(AppState::InGame).and_then(resource_exists::<Clocks>()))
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup);
+    }
+}
+
+// Constants
+
+const ACTIVE_COLOR: Color = Color::rgba(0.05, 0.05, 0.65, 0.8);
+const INACTIVE_COLOR: Color = Color::rgba(0.05, 0.05, 0.25, 0.8);
+const LOW_TIME: Duration = Duration::from_secs(10);
+const PLINTH_MARGIN: f32 = 8.0;
+
+// Structs
+
+#[derive(Clone, Copy)]
+struct PlayerClock {
+    // Byo-yomi periods that haven't been started yet
+    periods: u32,
+    overtime: bool,
+    remaining: Duration,
+}
+
+impl PlayerClock {
+    fn new(time_control: TimeControl) -> Option<Self> {
+        let (base, periods) = match time_control {
+            TimeControl::Unlimited => return None,
+            TimeControl::Fischer { base, .. } => (base, 0),
+            TimeControl::ByoYomi { base, periods, .. } => (base, periods),
+        };
+        Some(Self { periods, overtime: false, remaining: Duration::from_secs(base) })
+    }
+    // The player who just moved gets their increment, or a fresh byo-yomi period
+    fn finish_turn(&mut self, time_control: TimeControl) {
+        match time_control {
+            TimeControl::Fischer { increment, .. } => self.remaining += Duration::from_secs(increment),
+            TimeControl::ByoYomi { period, .. } if self.overtime => self.remaining = Duration::from_secs(period),
+            _ => {}
+        }
+    }
+    // Returns whether the player ran out of time, each time the base or a period runs out the next
+    // period is started instead if there's one left
+    fn run(&mut self, time_control: TimeControl, delta: Duration) -> bool {
+        self.remaining = self.remaining.saturating_sub(delta);
+        if !self.remaining.is_zero() {
+            return false;
+        }
+
+        match time_control {
+            TimeControl::ByoYomi { period, .. } if self.periods > 0 => {
+                self.periods -= 1;
+                self.overtime = true;
+                self.remaining = Duration::from_secs(period);
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+// Resources
+
+#[derive(Resource)]
+pub struct Clocks {
+    clocks: [PlayerClock; 2],
+    last_turn: Option<Turn>,
+    time_control: TimeControl,
+}
+
+impl Clocks {
+    // The base time or the current byo-yomi period, without the periods still to come
+    pub fn get_remaining(&self, turn: Turn) -> Duration {
+        self.clocks[index(turn)].remaining
+    }
+    pub fn get_time_control(&self) -> TimeControl {
+        self.time_control
+    }
+}
+
+// Components
+
+#[derive(Component)]
+struct ClockMarker;
+
+#[derive(Component)]
+struct ClockText {
+    turn: Turn,
+}
+
+// Systems
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<ClockMarker>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<Clocks>();
+}
+
+// The clocks sit just under the turn indicator, wherever the camera sees it from
+fn place_clocks(
+    mut clock_query: Query<(&Node, &mut Style), With<ClockMarker>>,
+    camera_query: Query<(&Camera, &Transform), With<BoardCamera>>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(viewport_size) = camera.logical_viewport_size() else {
+        return;
+    };
+    // Like the board labels, this runs before transforms propagate
+    let camera_transform = GlobalTransform::from(*camera_transform);
+
+    let (_, corner) = TURN_INDICATOR;
+    let corners: Vec<Vec2> = [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)]
+        .into_iter()
+        .filter_map(|(x, z)| camera.world_to_viewport(&camera_transform, corner * Vec3::new(x, 1.0, z)))
+        .collect();
+    if corners.is_empty() {
+        return;
+    }
+    let left = corners.iter().map(|x| x.x).fold(f32::MAX, f32::min);
+    let right = corners.iter().map(|x| x.x).fold(f32::MIN, f32::max);
+    let bottom = corners.iter().map(|x| x.y).fold(f32::MIN, f32::max);
+
+    for (node, mut style) in clock_query.iter_mut() {
+        let size = node.size();
+        style.left = Val::Px(((left + right - size.x) / 2.0).min(viewport_size.x - size.x).max(0.0));
+        style.top = Val::Px((bottom + PLINTH_MARGIN).min(viewport_size.y - size.y).max(0.0));
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    network_session: Option<Res<NetworkSession>>,
+    puzzle: Option<Res<Puzzle>>,
+    rules: Res<Rules>,
+) {
+    // Network games use the host's rules, puzzles are never timed
+    let time_control = network_session.map_or(rules.time_control, |x| x.get_rules().time_control);
+    let Some(clock) = PlayerClock::new(time_control) else {
+        return;
+    };
+    if puzzle.is_some() {
+        return;
+    }
+
+    commands.insert_resource(Clocks {
+        clocks: [clock; 2],
+        last_turn: None,
+        time_control,
+    });
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            ClockMarker,
+        ))
+        .with_children(|parent| {
+            for turn in [Turn::P1, Turn::P2] {
+                parent.spawn((
+                    TextBundle::from_section("", TextStyle {
+                        font_size: 28.0,
+                        ..default()
+                    })
+                        .with_style(Style {
+                            margin: UiRect::horizontal(Val::Px(5.0)),
+                            padding: UiRect::axes(Val::Px(10.0), Val::Px(2.0)),
+                            ..default()
+                        })
+                        .with_background_color(INACTIVE_COLOR),
+                    ClockText { turn },
+                ));
+            }
+        });
+}
+
+fn tick_clocks(
+    mut board: ResMut<Board>,
+    mut clocks: ResMut<Clocks>,
+    controllers: Res<Controllers>,
+    paused: Res<Paused>,
+    time: Res<Time>,
+) {
+    let turn = *board.get_turn();
+    if !matches!(turn, Turn::P1 | Turn::P2) {
+        return;
+    }
+    let Clocks { clocks, last_turn, time_control } = &mut *clocks;
+
+    if let Some(last_turn) = last_turn.filter(|x| *x != turn) {
+        clocks[index(last_turn)].finish_turn(*time_control);
+    }
+    *last_turn = Some(turn);
+
+    // Pausing only stops the game locally, so it can't stop the clocks of a network game
+    let network = controllers.p1 == Controller::Network || controllers.p2 == Controller::Network;
+    if paused.value && !network {
+        return;
+    }
+    // Network opponents run out of time on their side, which then resigns for them
+    let controller = if turn == Turn::P1 { controllers.p1 } else { controllers.p2 };
+    if clocks[index(turn)].run(*time_control, time.delta()) && controller != Controller::Network {
+        board.forfeit(WinReason::Timeout);
+    }
+}
+
+fn update_clock_texts(
+    mut text_query: Query<(&mut Text, &mut BackgroundColor, &ClockText)>,
+    board: Res<Board>,
+    clocks: Res<Clocks>,
+    theme: Res<Theme>,
+) {
+    for (mut text, mut background_color, ClockText { turn }) in text_query.iter_mut() {
+        let clock = &clocks.clocks[index(*turn)];
+        let seconds = clock.remaining.as_secs_f32().ceil() as u64;
+        let value = if clock.overtime {
+            format!("{}:{:02} ({})", seconds / 60, seconds % 60, clock.periods)
+        } else {
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        };
+
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        text.sections[0].style.color = if clock.remaining < LOW_TIME {
+            Color::RED
+        } else {
+            theme.palette().player(*turn)
+        };
+        *background_color = if board.get_turn() == turn { ACTIVE_COLOR } else { INACTIVE_COLOR }.into();
+    }
+}
+
+// Functions
+
+fn index(turn: Turn) -> usize {
+    match turn {
+        Turn::P2 | Turn::WinP2 => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fischer_adds_the_increment_and_flags_at_zero() {
+        let time_control = TimeControl::Fischer { base: 10, increment: 2 };
+        let mut clock = PlayerClock::new(time_control).unwrap();
+
+        assert!(!clock.run(time_control, Duration::from_secs(9)));
+        clock.finish_turn(time_control);
+        assert_eq!(clock.remaining, Duration::from_secs(3));
+        assert!(!clock.run(time_control, Duration::from_millis(2999)));
+        assert!(clock.run(time_control, Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn byo_yomi_gives_each_period_once() {
+        let time_control = TimeControl::ByoYomi { base: 10, period: 5, periods: 3 };
+        let mut clock = PlayerClock::new(time_control).unwrap();
+
+        assert!(!clock.run(time_control, Duration::from_secs(10)));
+        assert!(clock.overtime);
+        assert_eq!(clock.periods, 2);
+
+        // Moving within a period keeps it
+        assert!(!clock.run(time_control, Duration::from_secs(4)));
+        clock.finish_turn(time_control);
+        assert_eq!(clock.remaining, Duration::from_secs(5));
+
+        assert!(!clock.run(time_control, Duration::from_secs(5)));
+        assert!(!clock.run(time_control, Duration::from_secs(5)));
+        assert_eq!(clock.periods, 0);
+        assert!(clock.run(time_control, Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn byo_yomi_without_periods_flags_with_the_base_time() {
+        let time_control = TimeControl::ByoYomi { base: 10, period: 5, periods: 0 };
+        let mut clock = PlayerClock::new(time_control).unwrap();
+
+        assert!(clock.run(time_control, Duration::from_secs(10)));
+        assert!(!clock.overtime);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on};
+use itertools::Itertools;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::{Controller, Controllers};
+use crate::{
+    AppState,
+    board::{Action, Board, Piece, PieceMarker, Turn, WinReason, neighbours},
+    menus::Paused,
+};
+
+pub struct EngineControllerPlugin;
+impl Plugin for EngineControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame),
+                spawn_controllers
+            )
+            .add_systems(Update,
+                run_controllers.run_if(in_state(AppState::InGame).and_then(is_controller_used))
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup);
+    }
+}
+
+// Constants
+
+pub const WIN: i32 = 1_000_000;
+
+// Components
+
+#[derive(Component)]
+struct EngineController {
+    depth: usize,
+    task: Option<Task<Option<Action>>>,
+    turn: Turn,
+}
+
+// Systems
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<EngineController>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn run_controllers(
+    mut board: ResMut<Board>,
+    mut controllers: Query<&mut EngineController>,
+    paused: Res<Paused>,
+) {
+    if paused.value {
+        return;
+    }
+
+    for mut controller in controllers.iter_mut() {
+        if controller.turn != *board.get_turn() {
+            continue;
+        }
+
+        if board.is_placing() {
+            let (row, column) = placement_square(&board);
+            board.place_next_worker(row, column);
+            break;
+        }
+
+        match controller.task.take() {
+            None => {
+                let mut position = board.clone();
+                position.clear_history();
+                let depth = controller.depth;
+                controller.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+                    search(&position, depth)
+                }));
+            }
+            Some(task) if task.is_finished() => match block_on(task) {
+                Some(action) => board.play(&action),
+                None => board.forfeit(WinReason::Blocked),
+            },
+            task => controller.task = task,
+        }
+
+        break;
+    }
+}
+
+fn spawn_controllers(
+    mut commands: Commands,
+    controllers: Res<Controllers>,
+) {
+    for (controller, turn) in [(&controllers.p1, Turn::P1), (&controllers.p2, Turn::P2)] {
+        if let Controller::Engine { depth } = *controller {
+            commands.spawn(EngineController {
+                depth,
+                task: None,
+                turn,
+            });
+        }
+    }
+}
+
+// Run conditions
+
+fn is_controller_used(
+    controllers: Res<Controllers>,
+) -> bool {
+    matches!(controllers.p1, Controller::Engine { .. }) || matches!(controllers.p2, Controller::Engine { .. })
+}
+
+// Functions
+
+fn evaluate(board: &Board) -> i32 {
+    let mut score = 0;
+    for PieceMarker { piece, row, column, height } in board.get_pieces() {
+        let Piece::Worker { turn } = piece else {
+            continue;
+        };
+
+        let mut value = match height {
+            2 => 30,
+            3 => 100,
+            _ => 0,
+        };
+        for (neighbour_row, neighbour_column) in neighbours(row, column) {
+            match board.get_top(neighbour_row, neighbour_column) {
+                Some(top_height) if top_height <= height => value += 5 * top_height as i32 + 2,
+                _ => {}
+            }
+        }
+
+        if turn == *board.get_turn() {
+            score += value;
+        } else {
+            score -= value;
+        }
+    }
+    score
+}
+
+// Once stopped, the search unwinds straight away and its result is meaningless
+fn negamax(board: &Board, depth: usize, mut alpha: i32, beta: i32, stop: &AtomicBool) -> i32 {
+    if stop.load(Ordering::Relaxed) {
+        return 0;
+    }
+    // The previous action won the game, so the side to move has lost
+    if matches!(board.get_turn(), Turn::WinP1 | Turn::WinP2) {
+        return -WIN - depth as i32;
+    }
+
+    let actions = board.get_actions();
+    if actions.is_empty() {
+        return -WIN - depth as i32;
+    }
+    if depth == 0 {
+        return evaluate(board);
+    }
+
+    for action in sort_actions(actions) {
+        let mut next = board.clone();
+        next.play(&action);
+
+        let score = -negamax(&next, depth - 1, -beta, -alpha, stop);
+        if score >= beta {
+            return score;
+        }
+        alpha = alpha.max(score);
+    }
+    alpha
+}
+
+pub fn placement_square(board: &Board) -> (usize, usize) {
+    (0..5)
+        .cartesian_product(0..5)
+        .filter(|(row, column)| board.get_top(*row, *column).is_some())
+        .min_by_key(|(row, column)| row.abs_diff(2) + column.abs_diff(2))
+        .unwrap()
+}
+
+pub fn search(board: &Board, depth: usize) -> Option<Action> {
+    search_scored(board, depth, &AtomicBool::new(false)).map(|(action, _)| action)
+}
+
+pub fn search_scored(board: &Board, depth: usize, stop: &AtomicBool) -> Option<(Action, i32)> {
+    let mut alpha = -2 * WIN;
+    let mut best = None;
+    for action in sort_actions(board.get_actions()) {
+        let mut next = board.clone();
+        next.play(&action);
+
+        let score = -negamax(&next, depth.saturating_sub(1), -2 * WIN, -alpha, stop);
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some(action);
+        }
+    }
+    best.map(|x| (x, alpha))
+}
+
+fn sort_actions(actions: Vec<Action>) -> Vec<Action> {
+    actions.into_iter().sorted_by_key(|x| std::cmp::Reverse(x.to.2)).collect()
+}
\ No newline at end of file
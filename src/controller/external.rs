@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{Mutex, mpsc::{self, Receiver, TryRecvError}},
+    thread,
+    time::Duration,
+};
+
+use super::{Controller, Controllers};
+use crate::{
+    AppState,
+    board::{Board, TimeControl, Turn, WinReason},
+    clock::Clocks,
+    engine_protocol::{PROTOCOL_NAME, SearchLimits},
+    menus::Paused,
+    notation::{format_position, parse_ply},
+};
+
+pub struct ExternalControllerPlugin;
+impl Plugin for ExternalControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame),
+                spawn_controllers
+            )
+            .add_systems(Update,
+                run_controllers.run_if(in_state(AppState::InGame).and_then(is_controller_used))
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup);
+    }
+}
+
+// Constants
+
+const MOVE_TIME: u64 = 2000;
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Structs
+
+struct EngineProcess {
+    child: Child,
+    lines: Mutex<Receiver<String>>,
+    stdin: ChildStdin,
+}
+
+impl EngineProcess {
+    fn spawn(command: &str) -> io::Result<Self> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or(io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            child,
+            lines: Mutex::new(receiver),
+            stdin,
+        })
+    }
+    fn quit(mut self) {
+        let _ = self.send("quit");
+        drop(self.stdin);
+
+        // Bots that ignore "quit" are killed instead of being left behind
+        let mut child = self.child;
+        thread::spawn(move || {
+            thread::sleep(QUIT_TIMEOUT);
+            if !matches!(child.try_wait(), Ok(Some(_))) {
+                let _ = child.kill();
+            }
+            let _ = child.wait();
+        });
+    }
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()
+    }
+}
+
+enum ExternalControllerState {
+    Handshake,
+    Ready,
+    Thinking,
+}
+
+// Resources
+
+#[derive(Resource)]
+pub struct ExternalBot {
+    pub command: String,
+}
+
+// Components
+
+#[derive(Component)]
+struct ExternalController {
+    process: Option<EngineProcess>,
+    state: ExternalControllerState,
+    turn: Turn,
+}
+
+// Systems
+
+fn cleanup(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut ExternalController)>,
+) {
+    for (entity, mut controller) in query.iter_mut() {
+        if let Some(process) = controller.process.take() {
+            process.quit();
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+fn run_controllers(
+    mut board: ResMut<Board>,
+    mut controllers: Query<&mut ExternalController>,
+    clocks: Option<Res<Clocks>>,
+    paused: Res<Paused>,
+) {
+    for mut controller in controllers.iter_mut() {
+        let ExternalController { process, state, turn } = &mut *controller;
+        let own_turn = *turn == *board.get_turn();
+
+        let Some(current) = process.as_mut() else {
+            if own_turn {
+                board.forfeit(WinReason::Disconnect);
+            }
+            continue;
+        };
+
+        let mut lines = Vec::new();
+        let disconnected = loop {
+            match current.lines.lock().unwrap().try_recv() {
+                Ok(line) => lines.push(line),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+
+        for line in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match (words.as_slice(), &state) {
+                ([ok], ExternalControllerState::Handshake) if *ok == format!("{}ok", PROTOCOL_NAME) => {
+                    *state = ExternalControllerState::Ready;
+                }
+                (["id", "name", name @ ..], _) => info!("Playing against {}", name.join(" ")),
+                (["info", info @ ..], _) => debug!("Bot: {}", info.join(" ")),
+                (["bestmove", ply], ExternalControllerState::Thinking) => {
+                    *state = ExternalControllerState::Ready;
+                    if !own_turn {
+                        continue;
+                    }
+                    match parse_ply(&board, ply) {
+                        Ok(ply) => board.apply(&ply),
+                        // Giving up is only being blocked when there's nothing left to play
+                        Err(_) if *ply == "none" => {
+                            let blocked = !board.is_placing() && board.get_actions().is_empty();
+                            board.forfeit(if blocked { WinReason::Blocked } else { WinReason::Resignation });
+                        }
+                        Err(error) => {
+                            warn!("The bot played an illegal ply: {}", error);
+                            board.forfeit(WinReason::Forfeit);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if disconnected {
+            error!("The bot exited unexpectedly");
+            if let Some(process) = process.take() {
+                process.quit();
+            }
+            continue;
+        }
+
+        if own_turn && !paused.value && matches!(state, ExternalControllerState::Ready) {
+            // Bots manage their own time when there's a clock, otherwise they're given a fixed time per move
+            let limits = match clocks.as_ref() {
+                Some(clocks) => {
+                    let increment = match clocks.get_time_control() {
+                        TimeControl::Fischer { increment, .. } => Some(increment * 1000),
+                        _ => None,
+                    };
+                    SearchLimits {
+                        gold_increment: increment,
+                        gold_time: Some(clocks.get_remaining(Turn::P1).as_millis() as u64),
+                        silver_increment: increment,
+                        silver_time: Some(clocks.get_remaining(Turn::P2).as_millis() as u64),
+                        ..default()
+                    }
+                }
+                None => SearchLimits {
+                    move_time: Some(MOVE_TIME),
+                    ..default()
+                },
+            };
+            let sent = current.send(&format!("position fen {}", format_position(&board)))
+                .and_then(|_| current.send(&limits.format()));
+            match sent {
+                Ok(()) => *state = ExternalControllerState::Thinking,
+                Err(error) => {
+                    error!("Couldn't talk to the bot: {}", error);
+                    if let Some(process) = process.take() {
+                        process.quit();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn spawn_controllers(
+    mut commands: Commands,
+    controllers: Res<Controllers>,
+    external_bot: Option<Res<ExternalBot>>,
+) {
+    for (controller, turn) in [(&controllers.p1, Turn::P1), (&controllers.p2, Turn::P2)] {
+        if *controller != Controller::External {
+            continue;
+        }
+
+        let process = match external_bot.as_ref().map(|x| EngineProcess::spawn(&x.command)) {
+            Some(Ok(mut process)) => process.send(PROTOCOL_NAME).map(|_| process).ok(),
+            Some(Err(error)) => {
+                error!("Couldn't start the bot: {}", error);
+                None
+            }
+            None => None,
+        };
+        commands.spawn(ExternalController {
+            process,
+            state: ExternalControllerState::Handshake,
+            turn,
+        });
+    }
+}
+
+// Run conditions
+
+fn is_controller_used(
+    controllers: Res<Controllers>,
+) -> bool {
+    controllers.p1 == Controller::External || controllers.p2 == Controller::External
+}
\ No newline at end of file
@@ -2,13 +2,15 @@ use bevy::prelude::*;
 use bevy_mod_picking::prelude::*;
 
 use bevy::utils::hashbrown::HashMap;
+use bevy_mod_picking::backends::raycast::bevy_mod_raycast::prelude::NoBackfaceCulling;
 use itertools::Itertools;
 use std::ops::Deref;
 
 use super::{Controller, Controllers};
 use crate::{
     AppState,
-    board::{Board, Piece, PieceMarker, Turn},
+    animation::Tween,
+    board::{Board, Ghosts, PickBlocker, Piece, PieceMarker, Turn, WinReason, neighbours},
     menus::Paused,
 };
 
@@ -30,7 +32,8 @@ impl Plugin for HumanControllerPlugin {
             .add_systems(Update, (
                 pause_pickable,
                 run_controllers,
-            ).run_if(in_state(AppState::InGame).and_then(is_controller_used)))
+                show_ghosts,
+            ).chain().run_if(in_state(AppState::InGame).and_then(is_controller_used)))
             .add_systems(OnExit(AppState::InGame), cleanup);
     }
 }
@@ -39,13 +42,13 @@ const BLOCK: Pickable = Pickable {
     should_block_lower: true,
     should_emit_events: false,
 };
+const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
 
 // Structs
 
 enum HumanControllerState {
     PrepPlaceWorker,
-    PlaceWorker1,
-    PlaceWorker2,
+    PlaceWorker,
     PrepMovement,
     Movement1,
     Movement2 {
@@ -57,11 +60,28 @@ enum HumanControllerState {
         selected_row: usize,
         selected_column: usize,
     },
-    Build,
+    Build {
+        selected_row: usize,
+        selected_column: usize,
+    },
+    BuildChoice {
+        selected_row: usize,
+        selected_column: usize,
+        selected_height: usize,
+    },
 }
 
 // Components
 
+#[derive(Component)]
+enum BuildChoiceButton {
+    Block,
+    Dome,
+}
+
+#[derive(Component)]
+struct BuildChoiceMarker;
+
 #[derive(Component)]
 struct HumanController {
     turn: Turn,
@@ -73,18 +93,17 @@ struct PauseBlockerMarker;
 
 // Systems
 
+#[allow(clippy::type_complexity)]
 fn cleanup(
     mut commands: Commands,
-    query: Query<Entity, Or<(With<HumanController>, With<PauseBlockerMarker>)>>,
+    query: Query<Entity, Or<(With<BuildChoiceMarker>, With<HumanController>, With<PauseBlockerMarker>)>>,
 ) {
     for entity in query.iter() {
-        commands.entity(entity).despawn();
+        commands.entity(entity).despawn_recursive();
     }
-
-    commands.remove_resource::<Controllers>();
 }
 
-fn guarantee_pickable(
+pub fn guarantee_pickable(
     mut commands: Commands,
     pickables_query: Query<Entity, (With<PieceMarker>, Without<Pickable>)>,
 ) {
@@ -100,7 +119,14 @@ fn handle_input(
     mut ev_clicked: EventWriter<Clicked>,
     mut pointer_down: EventReader<Pointer<Down>>,
     pieces_query: Query<(Entity, &PieceMarker)>,
+    tween_query: Query<(), With<Tween>>,
 ) {
+    // Clicks are ignored until the pieces have settled
+    if !tween_query.is_empty() {
+        pointer_down.clear();
+        return;
+    }
+
     let pieces: HashMap<Entity, &PieceMarker> = pieces_query.iter().collect();
 
     for Pointer {
@@ -142,7 +168,10 @@ fn pause_pickable(
                     material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.0).into()),
                     ..default()
                 },
+                // Still blocks from the inside once the camera is zoomed in
+                NoBackfaceCulling,
                 PauseBlockerMarker,
+                PickBlocker,
             ));
         }
     } else {
@@ -152,15 +181,20 @@ fn pause_pickable(
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn run_controllers(
     mut board: ResMut<Board>,
+    mut commands: Commands,
     mut controllers: Query<&mut HumanController>,
     mut ev_clicked: EventReader<Clicked>,
     mut pieces_query: Query<(&PieceMarker, &mut Pickable, &mut Transform)>,
+    build_choice_query: Query<(&Interaction, &BuildChoiceButton), Changed<Interaction>>,
+    build_choice_marker_query: Query<Entity, With<BuildChoiceMarker>>,
 ) {
     const SELECT: fn(usize, usize, &mut ResMut<Board>, &mut HashMap<(usize, usize, usize), (Mut<Pickable>, Mut<Transform>)>) =
     |row, column, board, world_pieces| {
-        if let Some(top_height) = board.get_top(row, column) {
+        if board.can_build(row, column) {
+            let top_height = board.get_top(row, column).unwrap();
             let (mut pickable, _) = world_pieces.remove(&(row, column, top_height)).unwrap();
             *pickable = Pickable::default();
         }
@@ -248,40 +282,29 @@ fn run_controllers(
 
         match controller.state {
             HumanControllerState::PrepPlaceWorker => {
+                if !board.is_placing() {
+                    controller.state = HumanControllerState::PrepMovement;
+                    break;
+                }
+
                 for (row, column) in (0..5).cartesian_product(0..5) {
-                    if board.get_top(row, column).is_some_and(|x| x == 0) {
-                        let (mut pickable, _) = world_pieces.remove(&(row, column, 0)).unwrap();
+                    if let Some(top_height) = board.get_top(row, column) {
+                        let (mut pickable, _) = world_pieces.remove(&(row, column, top_height)).unwrap();
                         *pickable = Pickable::default();
                     }
                 }
 
-                controller.state = HumanControllerState::PlaceWorker1;
+                controller.state = HumanControllerState::PlaceWorker;
             }
-            HumanControllerState::PlaceWorker1 => {
-                for Clicked { row, column, height } in ev_clicked.read() {
-                    board.place_worker(*row, *column, height + 1, controller.turn);
-
-                    let (mut pickable, _) = world_pieces.remove(&(*row, *column, *height)).unwrap();
-                    *pickable = BLOCK;
-
-                    controller.state = HumanControllerState::PlaceWorker2;
-
-                    break;
-                }
-                ev_clicked.clear();
-            }
-            HumanControllerState::PlaceWorker2 => {
-                for Clicked { row, column, height } in ev_clicked.read() {
-                    board.place_worker(*row, *column, height + 1, controller.turn);
+            HumanControllerState::PlaceWorker => {
+                if let Some(Clicked { row, column, height: _ }) = ev_clicked.read().next() {
+                    board.place_next_worker(*row, *column);
 
                     for (mut pickable, _) in world_pieces.into_values() {
                         *pickable = BLOCK;
                     }
 
-                    controller.state = HumanControllerState::PrepMovement;
-                    board.next_turn();
-
-                    break;
+                    controller.state = HumanControllerState::PrepPlaceWorker;
                 }
                 ev_clicked.clear();
             }
@@ -303,7 +326,7 @@ fn run_controllers(
                 controller.state = HumanControllerState::Movement1;
             }
             HumanControllerState::Movement1 => {
-                for Clicked { row, column, height } in ev_clicked.read() {
+                if let Some(Clicked { row, column, height }) = ev_clicked.read().next() {
                     let (mut pickable, mut transform) = world_pieces.remove(&(*row, *column, *height)).unwrap();
                     *pickable = BLOCK;
                     transform.translation.y += RAISE;
@@ -315,13 +338,11 @@ fn run_controllers(
                         selected_column: *column,
                         selected_height: *height,
                     };
-
-                    break;
                 }
                 ev_clicked.clear();
             }
             HumanControllerState::Movement2 { selected_row, selected_column, selected_height } => {
-                for Clicked { row, column, height } in ev_clicked.read() {
+                if let Some(Clicked { row, column, height }) = ev_clicked.read().next() {
                     for (ref mut pickable, _) in world_pieces.values_mut() {
                         **pickable = BLOCK;
                     }
@@ -345,9 +366,9 @@ fn run_controllers(
                             };
                         }
                         _ => {
-                            board.movement(
-                                selected_row, selected_column, selected_height,
-                                *row, *column, height + 1);
+                            board.move_worker(
+                                (selected_row, selected_column, selected_height),
+                                (*row, *column, height + 1));
 
                             controller.state = HumanControllerState::PrepBuild {
                                 selected_row: *row,
@@ -355,30 +376,64 @@ fn run_controllers(
                             };
                         }
                     }
-
-                    break;
                 }
                 ev_clicked.clear();
             }
             HumanControllerState::PrepBuild { selected_row, selected_column } => {
+                // A worker that can't build after moving loses (only possible with a limited supply)
+                if !neighbours(selected_row, selected_column).any(|(row, column)| board.can_build(row, column)) {
+                    board.forfeit(WinReason::Blocked);
+                    break;
+                }
+
                 SELECT_NEIGHBOURS(selected_row, selected_column, &mut board, &mut world_pieces);
 
-                controller.state = HumanControllerState::Build;
+                controller.state = HumanControllerState::Build { selected_row, selected_column };
             }
-            HumanControllerState::Build => {
-                for Clicked { row, column, height } in ev_clicked.read() {
-                    board.build(*row, *column, height + 1);
-
+            HumanControllerState::Build { .. } => {
+                if let Some(Clicked { row, column, height }) = ev_clicked.read().next() {
                     for (mut pickable, _) in world_pieces.into_values() {
                         *pickable = BLOCK;
                     }
 
-                    controller.state = HumanControllerState::PrepMovement;
-                    board.next_turn();
+                    match (board.can_build_block(*row, *column), board.can_build_dome(*row, *column)) {
+                        (true, true) => {
+                            spawn_build_choice(&mut commands);
+                            controller.state = HumanControllerState::BuildChoice {
+                                selected_row: *row,
+                                selected_column: *column,
+                                selected_height: height + 1,
+                            };
+                        }
+                        (false, true) => {
+                            board.build_after_move(*row, *column, height + 1, true);
+                            controller.state = HumanControllerState::PrepMovement;
+                        }
+                        _ => {
+                            board.build_after_move(*row, *column, height + 1, false);
+                            controller.state = HumanControllerState::PrepMovement;
+                        }
+                    }
+                }
+                ev_clicked.clear();
+            }
+            HumanControllerState::BuildChoice { selected_row, selected_column, selected_height } => {
+                ev_clicked.clear();
+
+                for (interaction, button) in build_choice_query.iter() {
+                    if *interaction != Interaction::Pressed {
+                        continue;
+                    }
 
+                    let dome = matches!(button, BuildChoiceButton::Dome);
+                    board.build_after_move(selected_row, selected_column, selected_height, dome);
+                    for entity in build_choice_marker_query.iter() {
+                        commands.entity(entity).despawn_recursive();
+                    }
+
+                    controller.state = HumanControllerState::PrepMovement;
                     break;
                 }
-                ev_clicked.clear();
             }
         }
 
@@ -386,6 +441,49 @@ fn run_controllers(
     }
 }
 
+// Previews where the selected worker can go, then where it can build
+fn show_ghosts(
+    mut ghosts: ResMut<Ghosts>,
+    board: Res<Board>,
+    controllers: Query<&HumanController>,
+) {
+    let mut pieces = Vec::new();
+    for controller in controllers.iter().filter(|x| x.turn == *board.get_turn()) {
+        match controller.state {
+            HumanControllerState::Movement2 { selected_row, selected_column, selected_height } => {
+                for (row, column) in neighbours(selected_row, selected_column) {
+                    if let Some(top_height) = board.get_top(row, column).filter(|x| *x <= selected_height) {
+                        pieces.push(PieceMarker {
+                            piece: Piece::Worker { turn: controller.turn },
+                            row,
+                            column,
+                            height: top_height + 1,
+                        });
+                    }
+                }
+            }
+            HumanControllerState::Build { selected_row, selected_column } => {
+                for (row, column) in neighbours(selected_row, selected_column) {
+                    let Some(top_height) = board.get_top(row, column).filter(|_| board.can_build(row, column)) else {
+                        continue;
+                    };
+                    pieces.push(PieceMarker {
+                        piece: if board.can_build_block(row, column) { Piece::Block } else { Piece::Dome },
+                        row,
+                        column,
+                        height: top_height + 1,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if ghosts.pieces != pieces {
+        ghosts.pieces = pieces;
+    }
+}
+
 fn spawn_controllers(
     mut commands: Commands,
     controllers: Res<Controllers>,
@@ -404,6 +502,53 @@ fn spawn_controllers(
     }
 }
 
+// Functions
+
+fn spawn_build_choice(
+    commands: &mut Commands,
+) {
+    let button_style = Style {
+        width: Val::Px(120.0),
+        height: Val::Px(50.0),
+        margin: UiRect::all(Val::Px(10.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 32.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                bottom: Val::Px(20.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        },
+        BuildChoiceMarker,
+    )).with_children(|parent| {
+        for (button, text) in [(BuildChoiceButton::Block, "Block"), (BuildChoiceButton::Dome, "Dome")] {
+            parent.spawn((
+                ButtonBundle {
+                    style: button_style.clone(),
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                },
+                button,
+            )).with_children(|parent| {
+                parent.spawn(TextBundle::from_section(text, button_text_style.clone()));
+            });
+        }
+    });
+}
+
 // Run conditions
 
 fn is_controller_used(
@@ -414,11 +559,7 @@ fn is_controller_used(
         p2,
     } = controllers.deref();
 
-    if *p1 == Controller::Human || *p2 == Controller::Human {
-        true
-    } else {
-        false
-    }
+    *p1 == Controller::Human || *p2 == Controller::Human
 }
 
 // Events
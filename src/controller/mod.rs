@@ -1,22 +1,37 @@
+mod engine;
+mod external;
 mod human;
 
 use bevy::prelude::*;
 
+use engine::EngineControllerPlugin;
+use external::ExternalControllerPlugin;
 use human::HumanControllerPlugin;
 
+pub use engine::{WIN, placement_square, search_scored};
+pub use external::ExternalBot;
+pub use human::guarantee_pickable;
+
 pub struct ControllersPlugin;
 impl Plugin for ControllersPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_plugins(HumanControllerPlugin);
+            .add_plugins((EngineControllerPlugin, ExternalControllerPlugin, HumanControllerPlugin));
     }
 }
 
 // Structs
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum Controller {
+    Engine {
+        depth: usize,
+    },
+    // Plies are played by the bot started with the --bot command line option
+    External,
     Human,
+    // Plies are played by the remote peer of the network session
+    Network,
 }
 
 // Resources
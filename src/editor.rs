@@ -0,0 +1,371 @@
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use crate::{
+    AppState,
+    board::{Board, Piece, PieceMarker, Rules, StartingPosition, Turn},
+    controller::{Controller, Controllers, guarantee_pickable},
+    puzzle::export_puzzle,
+};
+
+pub struct EditorPlugin;
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::Editor), setup)
+            .add_systems(PreUpdate, (
+                guarantee_pickable,
+                apply_deferred,
+                unblock_pieces,
+                handle_input,
+            ).chain().run_if(in_state(AppState::Editor)))
+            .add_systems(Update, (
+                buttons_system,
+                update_labels,
+            ).run_if(in_state(AppState::Editor)))
+            .add_systems(OnExit(AppState::Editor), cleanup);
+    }
+}
+
+// Constants
+
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.65);
+const MAX_TURNS: usize = 5;
+const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
+const SELECTED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.45, 0.05);
+
+// Structs
+
+#[derive(Clone, Copy, PartialEq)]
+enum EditorTool {
+    Dome,
+    Lower,
+    Raise,
+    Worker {
+        turn: Turn,
+    },
+}
+
+// Resources
+
+#[derive(Resource)]
+struct EditorState {
+    status: String,
+    tool: EditorTool,
+    turns: usize,
+}
+
+// Components
+
+#[derive(Clone, Copy, Component, PartialEq)]
+enum EditorButton {
+    Export,
+    MainMenu,
+    Play,
+    Tool(EditorTool),
+    Turn,
+    Turns,
+}
+
+#[derive(Component)]
+struct EditorMarker;
+
+#[derive(Component)]
+struct StatusText;
+
+// Systems
+
+fn buttons_system(
+    mut board: ResMut<Board>,
+    mut commands: Commands,
+    mut editor_state: ResMut<EditorState>,
+    mut interaction_query: Query<(&Interaction, &EditorButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    rules: Res<Rules>,
+) {
+    for (interaction, button) in interaction_query.iter_mut() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match *button {
+            EditorButton::Export => {
+                editor_state.status = match export_puzzle(&board, editor_state.turns) {
+                    Ok(puzzle) => format!("Exported \"{}\"", puzzle.name),
+                    Err(error) => format!("Couldn't export: {}", error),
+                };
+            }
+            EditorButton::MainMenu => next_state.set(AppState::Menu),
+            EditorButton::Play => {
+                let counts = [Turn::P1, Turn::P2].map(|turn| count_workers(&board, turn));
+                if counts.contains(&1) {
+                    editor_state.status = "Each player needs zero or two workers".to_string();
+                    continue;
+                }
+
+                let mut board = board.clone();
+                let starter = *board.get_turn();
+                board.apply_rules(starter, &rules);
+
+                commands.insert_resource(Controllers {
+                    p1: Controller::Human,
+                    p2: Controller::Human,
+                });
+                commands.insert_resource(StartingPosition { board });
+                next_state.set(AppState::InGame);
+            }
+            EditorButton::Tool(tool) => editor_state.tool = tool,
+            EditorButton::Turn => {
+                let turn = if *board.get_turn() == Turn::P1 { Turn::P2 } else { Turn::P1 };
+                board.set_turn(turn);
+            }
+            EditorButton::Turns => editor_state.turns = editor_state.turns % MAX_TURNS + 1,
+        }
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<EditorMarker>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<EditorState>();
+}
+
+fn handle_input(
+    mut board: ResMut<Board>,
+    mut editor_state: ResMut<EditorState>,
+    mut pointer_down: EventReader<Pointer<Down>>,
+    buttons_query: Query<&Interaction, With<EditorButton>>,
+    pieces_query: Query<&PieceMarker>,
+) {
+    // Clicks on the panel also reach the board behind it
+    if buttons_query.iter().any(|x| *x != Interaction::None) {
+        pointer_down.clear();
+        return;
+    }
+
+    for Pointer {
+        pointer_id: _,
+        pointer_location: _,
+        target,
+        event,
+    } in pointer_down.read() {
+        if event.button != PointerButton::Primary {
+            continue;
+        }
+        if let Ok(PieceMarker { piece: _, row, column, height: _ }) = pieces_query.get(*target) {
+            editor_state.status = apply_tool(&mut board, editor_state.tool, *row, *column)
+                .err()
+                .unwrap_or_default()
+                .to_string();
+        }
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+) {
+    commands.insert_resource(EditorState {
+        status: String::new(),
+        tool: EditorTool::Raise,
+        turns: 1,
+    });
+
+    const BASE_COLOR: Color = Color::rgba(0.97, 0.97, 1.00, 0.8);
+
+    let button_style = Style {
+        width: Val::Px(180.0),
+        height: Val::Px(36.0),
+        margin: UiRect::all(Val::Px(4.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 22.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(5.0),
+                    top: Val::Px(5.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BASE_COLOR.into(),
+                ..default()
+            },
+            EditorMarker,
+        ))
+        .with_children(|parent| {
+            for button in [
+                EditorButton::Tool(EditorTool::Raise),
+                EditorButton::Tool(EditorTool::Lower),
+                EditorButton::Tool(EditorTool::Dome),
+                EditorButton::Tool(EditorTool::Worker { turn: Turn::P1 }),
+                EditorButton::Tool(EditorTool::Worker { turn: Turn::P2 }),
+                EditorButton::Turn,
+                EditorButton::Turns,
+                EditorButton::Export,
+                EditorButton::Play,
+                EditorButton::MainMenu,
+            ] {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: NORMAL_BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        button,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section("", button_text_style.clone()));
+                    });
+            }
+        });
+
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section("", TextStyle {
+                color: Color::WHITE,
+                font_size: 20.0,
+                ..default()
+            }),
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(5.0),
+                bottom: Val::Px(5.0),
+                ..default()
+            },
+            ..default()
+        },
+        StatusText,
+        EditorMarker,
+    ));
+}
+
+fn unblock_pieces(
+    mut pickables_query: Query<&mut Pickable, (With<PieceMarker>, Added<Pickable>)>,
+) {
+    for mut pickable in pickables_query.iter_mut() {
+        *pickable = Pickable::default();
+    }
+}
+
+fn update_labels(
+    mut buttons_query: Query<(&EditorButton, &Interaction, &mut BackgroundColor, &Children)>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+    mut text_query: Query<&mut Text, Without<StatusText>>,
+    board: Res<Board>,
+    editor_state: Res<EditorState>,
+) {
+    for (button, interaction, mut color, children) in buttons_query.iter_mut() {
+        *color = match (*button, *interaction) {
+            (EditorButton::Tool(tool), _) if tool == editor_state.tool => SELECTED_BUTTON_COLOR.into(),
+            (_, Interaction::Hovered | Interaction::Pressed) => HOVERED_BUTTON_COLOR.into(),
+            _ => NORMAL_BUTTON_COLOR.into(),
+        };
+
+        let label = match *button {
+            EditorButton::Export => "Export".to_string(),
+            EditorButton::MainMenu => "Main Menu".to_string(),
+            EditorButton::Play => "Play".to_string(),
+            EditorButton::Tool(EditorTool::Dome) => "Dome".to_string(),
+            EditorButton::Tool(EditorTool::Lower) => "Lower".to_string(),
+            EditorButton::Tool(EditorTool::Raise) => "Raise".to_string(),
+            EditorButton::Tool(EditorTool::Worker { turn: Turn::P1 }) => "Gold worker".to_string(),
+            EditorButton::Tool(EditorTool::Worker { turn: _ }) => "Silver worker".to_string(),
+            EditorButton::Turn => match board.get_turn() {
+                Turn::P2 => "Silver to move".to_string(),
+                _ => "Gold to move".to_string(),
+            },
+            EditorButton::Turns => format!("Win in {}", editor_state.turns),
+        };
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            if text.sections[0].value != label {
+                text.sections[0].value = label;
+            }
+        }
+    }
+
+    if editor_state.is_changed() {
+        for mut text in status_query.iter_mut() {
+            text.sections[0].value = editor_state.status.clone();
+        }
+    }
+}
+
+// Functions
+
+fn apply_tool(board: &mut Board, tool: EditorTool, row: usize, column: usize) -> Result<(), &'static str> {
+    let worker = (1..5).find_map(|height| match board.get_piece(row, column, height) {
+        Some(Piece::Worker { turn }) => Some((height, *turn)),
+        _ => None,
+    });
+    let dome = (1..5).find(|height| board.get_piece(row, column, *height) == Some(&Piece::Dome));
+
+    match (tool, worker) {
+        (EditorTool::Raise, Some((height, turn))) if height < 3 => {
+            board.remove(row, column, height);
+            board.build(row, column, height);
+            board.place_worker(row, column, height + 1, turn);
+        }
+        (EditorTool::Raise, None) => match board.get_top(row, column) {
+            Some(top_height) if top_height < 3 => board.build(row, column, top_height + 1),
+            Some(_) => return Err("Use the dome tool to complete a tower"),
+            None => return Err("Domed towers can't be raised"),
+        },
+        (EditorTool::Lower, Some((height, turn))) if height > 1 => {
+            board.remove(row, column, height);
+            board.remove(row, column, height - 1);
+            board.place_worker(row, column, height - 1, turn);
+        }
+        (EditorTool::Lower, None) => match board.get_top(row, column) {
+            _ if dome.is_some() => board.remove(row, column, dome.unwrap()),
+            Some(top_height) if top_height > 0 => board.remove(row, column, top_height),
+            _ => return Err("The square is already at ground level"),
+        },
+        (EditorTool::Dome, None) => match (dome, board.get_top(row, column)) {
+            (Some(height), _) => board.remove(row, column, height),
+            (None, Some(top_height)) => board.build_dome(row, column, top_height + 1),
+            _ => unreachable!(),
+        },
+        (EditorTool::Dome, Some(_)) => return Err("Domes can't be placed on workers"),
+        (EditorTool::Worker { turn }, Some((height, worker_turn))) => {
+            if worker_turn != turn && count_workers(board, turn) >= 2 {
+                return Err("Each player has at most two workers");
+            }
+
+            board.remove(row, column, height);
+            if worker_turn != turn {
+                board.place_worker(row, column, height, turn);
+            }
+        }
+        (EditorTool::Worker { turn }, None) => match board.get_top(row, column) {
+            _ if count_workers(board, turn) >= 2 => return Err("Each player has at most two workers"),
+            Some(top_height) if top_height < 3 => board.place_worker(row, column, top_height + 1, turn),
+            _ => return Err("Workers can't be placed there"),
+        },
+        (_, Some(_)) => return Err("Workers can't go any higher or lower"),
+    }
+
+    Ok(())
+}
+
+fn count_workers(board: &Board, turn: Turn) -> usize {
+    board.get_pieces()
+        .iter()
+        .filter(|x| x.piece == Piece::Worker { turn })
+        .count()
+}
\ No newline at end of file
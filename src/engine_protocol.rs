@@ -0,0 +1,221 @@
+use std::{
+    io::{self, BufRead, Write},
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    board::{Board, Ply, Rules, Turn},
+    controller::{WIN, placement_square, search_scored},
+    notation::{format_ply, parse_ply, parse_position},
+};
+
+// Engines talk over stdin and stdout, one command per line:
+//
+// sep                                   -> id name <name>, id author <author>, sepok
+// isready                               -> readyok
+// newgame
+// position startpos [moves <ply>...]
+// position fen <position> [moves <ply>...]
+// go [gtime <ms>] [stime <ms>] [ginc <ms>] [sinc <ms>] [movetime <ms>] [depth <n>]
+//                                       -> info depth <n> score <score> time <ms> pv <ply>...,
+//                                          bestmove <ply>|none
+// stop                                  -> bestmove <ply>|none
+// quit
+//
+// Positions and plies use the notation module, and "bestmove none" forfeits the game. The search runs
+// in the background, so "stop" cuts it short and answers with the best ply of the last finished depth
+
+// Constants
+
+const BRANCHING_FACTOR: u32 = 16;
+const DEFAULT_DEPTH: usize = 3;
+const MAX_DEPTH: usize = 12;
+pub const PROTOCOL_NAME: &str = "sep";
+
+// Structs
+
+#[derive(Default)]
+pub struct SearchLimits {
+    pub depth: Option<usize>,
+    pub gold_increment: Option<u64>,
+    pub gold_time: Option<u64>,
+    pub move_time: Option<u64>,
+    pub silver_increment: Option<u64>,
+    pub silver_time: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn format(&self) -> String {
+        let mut text = "go".to_string();
+        for (name, value) in [
+            ("gtime", self.gold_time),
+            ("stime", self.silver_time),
+            ("ginc", self.gold_increment),
+            ("sinc", self.silver_increment),
+            ("movetime", self.move_time),
+            ("depth", self.depth.map(|x| x as u64)),
+        ] {
+            if let Some(value) = value {
+                text += &format!(" {} {}", name, value);
+            }
+        }
+        text
+    }
+    pub fn parse(arguments: &[&str]) -> Result<Self, String> {
+        let mut limits = SearchLimits::default();
+        for pair in arguments.chunks(2) {
+            let [name, value] = pair else {
+                return Err(format!("\"{}\" has no value", pair[0]));
+            };
+            let value = value.parse::<u64>().map_err(|_| format!("\"{}\" isn't a number", value))?;
+            match *name {
+                "gtime" => limits.gold_time = Some(value),
+                "stime" => limits.silver_time = Some(value),
+                "ginc" => limits.gold_increment = Some(value),
+                "sinc" => limits.silver_increment = Some(value),
+                "movetime" => limits.move_time = Some(value),
+                "depth" => limits.depth = Some(value as usize),
+                _ => return Err(format!("unknown limit \"{}\"", name)),
+            }
+        }
+        Ok(limits)
+    }
+    fn budget(&self, turn: Turn) -> Option<Duration> {
+        let (time, increment) = match turn {
+            Turn::P2 => (self.silver_time, self.silver_increment),
+            _ => (self.gold_time, self.gold_increment),
+        };
+
+        self.move_time
+            .or(time.map(|x| (x / 20 + increment.unwrap_or(0) / 2).min(x / 2)))
+            .map(Duration::from_millis)
+    }
+}
+
+// Functions
+
+pub fn parse_position_command(arguments: &[&str]) -> Result<Board, String> {
+    let (mut board, rest) = match arguments {
+        ["startpos", rest @ ..] => (Board::new(&Rules::default()), rest),
+        ["fen", rest @ ..] => {
+            let end = rest.iter().position(|x| *x == "moves").unwrap_or(rest.len());
+            (parse_position(&rest[..end].join(" "))?, &rest[end..])
+        }
+        _ => return Err("expected \"startpos\" or \"fen\"".to_string()),
+    };
+
+    match rest {
+        [] => {}
+        ["moves", plies @ ..] => for ply in plies {
+            let ply = parse_ply(&board, ply)?;
+            board.apply(&ply);
+        },
+        _ => return Err("expected \"moves\"".to_string()),
+    }
+    Ok(board)
+}
+
+pub fn run() {
+    let mut board = Board::new(&Rules::default());
+    let mut search: Option<(Arc<AtomicBool>, JoinHandle<()>)> = None;
+    let mut stdout = io::stdout();
+
+    for line in io::stdin().lock().lines().map_while(Result::ok) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [PROTOCOL_NAME] => {
+                println!("id name rusty-santorini {}", env!("CARGO_PKG_VERSION"));
+                println!("id author rusty-santorini contributors");
+                println!("{}ok", PROTOCOL_NAME);
+            }
+            ["isready"] => println!("readyok"),
+            ["newgame"] => board = Board::new(&Rules::default()),
+            ["position", arguments @ ..] => match parse_position_command(arguments) {
+                Ok(position) => board = position,
+                Err(error) => println!("info string {}", error),
+            },
+            ["go", arguments @ ..] => match SearchLimits::parse(arguments) {
+                Ok(limits) => {
+                    stop_search(&mut search);
+                    let position = board.clone();
+                    let stop = Arc::new(AtomicBool::new(false));
+                    // Deeper searches are only started while they seem to fit in the budget, and this
+                    // stops one that doesn't once the budget runs out
+                    if let Some(budget) = limits.budget(*board.get_turn()) {
+                        let flag = stop.clone();
+                        thread::spawn(move || {
+                            thread::sleep(budget);
+                            flag.store(true, Ordering::Relaxed);
+                        });
+                    }
+                    let flag = stop.clone();
+                    search = Some((stop, thread::spawn(move || {
+                        let best = go(&position, &limits, &flag);
+                        println!("bestmove {}", best.map_or("none".to_string(), |x| format_ply(&x)));
+                    })));
+                }
+                Err(error) => println!("info string {}", error),
+            },
+            ["quit"] => break,
+            ["stop"] => stop_search(&mut search),
+            [] => {}
+            [command, ..] => println!("info string unknown command \"{}\"", command),
+        }
+        let _ = stdout.flush();
+    }
+    stop_search(&mut search);
+}
+
+fn go(board: &Board, limits: &SearchLimits, stop: &AtomicBool) -> Option<Ply> {
+    if !matches!(board.get_turn(), Turn::P1 | Turn::P2) {
+        return None;
+    }
+    if board.is_placing() {
+        let (row, column) = placement_square(board);
+        return Some(Ply::Placement { row, column });
+    }
+
+    let start = Instant::now();
+    let budget = limits.budget(*board.get_turn());
+    let max_depth = limits.depth.unwrap_or(if budget.is_some() { MAX_DEPTH } else { DEFAULT_DEPTH });
+
+    // Iterative deepening, stopping when the next depth likely won't fit in the budget
+    // The first depth always finishes, so that there is a ply to answer with
+    let mut best = None;
+    let unstoppable = AtomicBool::new(false);
+    for depth in 1..=max_depth {
+        let iteration = Instant::now();
+        let Some((action, score)) = search_scored(board, depth, if depth == 1 { &unstoppable } else { stop }) else {
+            break;
+        };
+        if depth > 1 && stop.load(Ordering::Relaxed) {
+            break;
+        }
+        best = Some(Ply::Action(action));
+        println!(
+            "info depth {} score {} time {} pv {}",
+            depth,
+            score,
+            start.elapsed().as_millis(),
+            format_ply(&Ply::Action(action)),
+        );
+
+        if score.abs() >= WIN {
+            break;
+        }
+        if budget.is_some_and(|x| start.elapsed() + iteration.elapsed() * BRANCHING_FACTOR > x) {
+            break;
+        }
+    }
+    best
+}
+
+// Waits for the answer of a running search, so that it always comes before the next command's
+fn stop_search(search: &mut Option<(Arc<AtomicBool>, JoinHandle<()>)>) {
+    if let Some((stop, handle)) = search.take() {
+        stop.store(true, Ordering::Relaxed);
+        let _ = handle.join();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,347 @@
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::Pickable;
+
+use bevy::{
+    pbr::{CascadeShadowConfigBuilder, NotShadowCaster, NotShadowReceiver},
+    render::{mesh::VertexAttributeValues, render_resource::Face},
+};
+use std::f32::consts::TAU;
+
+use crate::{
+    AppState,
+    camera::BoardCamera,
+};
+
+// The board sits on an island in the sea under a sky that goes through a day and night. All of it is
+// only drawn at high quality, low quality drops the shadows and antialiasing as well to suit
+// integrated graphics
+
+pub struct EnvironmentPlugin;
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GraphicsQuality>()
+            .add_systems(Update, (
+                sync_environment,
+                apply_deferred,
+                animate_sea,
+                cycle_daylight,
+            ).chain().run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor))))
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(OnExit(AppState::Editor), cleanup);
+    }
+}
+
+// Constants
+
+// Seconds for a whole day and night
+const DAY_LENGTH: f32 = 300.0;
+// Games start in the morning, as a fraction of the day from midnight
+const DAY_START: f32 = 0.3;
+const DAY_SKY_COLOR: Color = Color::WHITE;
+const DUSK_SKY_COLOR: Color = Color::rgb(1.0, 0.62, 0.45);
+const HORIZON_COLOR: Color = Color::rgb(0.78, 0.88, 0.95);
+const MOON_ILLUMINANCE: f32 = 1500.0;
+const NIGHT_SKY_COLOR: Color = Color::rgb(0.06, 0.08, 0.2);
+const SAND_COLOR: Color = Color::rgb(0.86, 0.78, 0.55);
+const SEA_COLOR: Color = Color::rgba(0.08, 0.32, 0.5, 0.85);
+const SEA_LEVEL: f32 = -1.0;
+const SEA_SIZE: f32 = 100.0;
+const SKY_RADIUS: f32 = 60.0;
+const SUN_ILLUMINANCE: f32 = 20000.0;
+const ZENITH_COLOR: Color = Color::rgb(0.25, 0.5, 0.9);
+
+// Resources
+
+#[derive(Clone, Copy, Default, PartialEq, Resource)]
+pub enum GraphicsQuality {
+    Low,
+    // The board on its own, as it has always looked
+    #[default]
+    Medium,
+    High,
+}
+
+// Components
+
+#[derive(Component)]
+struct EnvironmentMarker;
+
+#[derive(Component)]
+struct Sea;
+
+#[derive(Component)]
+struct Sky;
+
+#[derive(Component)]
+struct Sun;
+
+// Systems
+
+fn animate_sea(
+    mut meshes: ResMut<Assets<Mesh>>,
+    sea_query: Query<&Handle<Mesh>, With<Sea>>,
+    time: Res<Time>,
+) {
+    let t = time.elapsed_seconds();
+    for handle in sea_query.iter() {
+        let Some(mesh) = meshes.get_mut(handle) else {
+            continue;
+        };
+
+        let mut normals = Vec::new();
+        if let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+            for position in positions.iter_mut() {
+                let (height, normal) = wave(position[0], position[2], t);
+                position[1] = height;
+                normals.push(normal.to_array());
+            }
+        }
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<EnvironmentMarker>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn cycle_daylight(
+    mut day_time: Local<Option<f32>>,
+    mut fog_query: Query<&mut FogSettings, With<BoardCamera>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut sun_query: Query<(&mut DirectionalLight, &mut Transform), With<Sun>>,
+    sky_query: Query<&Handle<StandardMaterial>, With<Sky>>,
+    time: Res<Time>,
+) {
+    let Ok((mut sun, mut transform)) = sun_query.get_single_mut() else {
+        return;
+    };
+    let day_time = day_time.get_or_insert(DAY_START);
+    *day_time = (*day_time + time.delta_seconds() / DAY_LENGTH).fract();
+
+    // The sun rises in the east at a quarter of the day and the moon takes over from it at night,
+    // lighting the board from the other side of the sky
+    let angle = (*day_time - 0.25) * TAU;
+    let elevation = angle.sin();
+    let daylight = smoothstep(-0.1, 0.25, elevation);
+    let dusk = (1.0 - elevation.abs() / 0.3).max(0.0) * daylight;
+
+    let direction = Vec3::new(angle.cos(), elevation.abs().max(0.15), 0.4).normalize();
+    *transform = Transform::from_translation(direction * 20.0).looking_at(Vec3::ZERO, Vec3::Y);
+    sun.illuminance = MOON_ILLUMINANCE + (SUN_ILLUMINANCE - MOON_ILLUMINANCE) * daylight;
+    sun.color = mix(mix(Color::rgb(0.6, 0.7, 1.0), Color::WHITE, daylight), DUSK_SKY_COLOR, dusk);
+
+    let tint = mix(mix(NIGHT_SKY_COLOR, DAY_SKY_COLOR, daylight), DUSK_SKY_COLOR, dusk * 0.7);
+    for handle in sky_query.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = tint;
+        }
+    }
+    for mut fog in fog_query.iter_mut() {
+        let [r, g, b, _] = tint.as_rgba_f32();
+        fog.color = HORIZON_COLOR * [r, g, b];
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sync_environment(
+    mut camera_query: Query<(Entity, Option<&FogSettings>), With<BoardCamera>>,
+    mut commands: Commands,
+    mut lights_query: Query<&mut PointLight>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut msaa: ResMut<Msaa>,
+    environment_query: Query<Entity, With<EnvironmentMarker>>,
+    quality: Res<GraphicsQuality>,
+) {
+    let high = *quality == GraphicsQuality::High;
+
+    let target = if *quality == GraphicsQuality::Low { Msaa::Off } else { Msaa::Sample4 };
+    if *msaa != target {
+        *msaa = target;
+    }
+    for mut light in lights_query.iter_mut() {
+        let shadows = *quality != GraphicsQuality::Low;
+        if light.shadows_enabled != shadows {
+            light.shadows_enabled = shadows;
+        }
+    }
+
+    // Fog hides where the sea ends
+    for (entity, fog) in camera_query.iter_mut() {
+        match (high, fog.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert(FogSettings {
+                    color: HORIZON_COLOR,
+                    falloff: FogFalloff::Linear { start: 20.0, end: SKY_RADIUS },
+                    ..default()
+                });
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<FogSettings>();
+            }
+            _ => {}
+        }
+    }
+
+    match (high, environment_query.is_empty()) {
+        (true, true) => spawn_environment(&mut commands, &mut materials, &mut meshes),
+        (false, false) => {
+            for entity in environment_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        _ => {}
+    }
+}
+
+// Functions
+
+fn mix(from: Color, to: Color, t: f32) -> Color {
+    let from = from.as_rgba_f32();
+    let to = to.as_rgba_f32();
+    Color::rgba(
+        from[0] + (to[0] - from[0]) * t,
+        from[1] + (to[1] - from[1]) * t,
+        from[2] + (to[2] - from[2]) * t,
+        from[3] + (to[3] - from[3]) * t,
+    )
+}
+
+fn smoothstep(from: f32, to: f32, x: f32) -> f32 {
+    let t = ((x - from) / (to - from)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn spawn_environment(
+    commands: &mut Commands,
+    materials: &mut Assets<StandardMaterial>,
+    meshes: &mut Assets<Mesh>,
+) {
+    // Sky, coloured from the horizon up to the zenith and tinted through the day
+    let mut sky_mesh: Mesh = shape::UVSphere { radius: SKY_RADIUS, sectors: 32, stacks: 16 }.into();
+    if let Some(VertexAttributeValues::Float32x3(positions)) = sky_mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        let colors: Vec<[f32; 4]> = positions
+            .iter()
+            .map(|x| mix(HORIZON_COLOR, ZENITH_COLOR, (x[1] / SKY_RADIUS).max(0.0).sqrt()).as_linear_rgba_f32())
+            .collect();
+        sky_mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(sky_mesh),
+            material: materials.add(StandardMaterial {
+                unlit: true,
+                fog_enabled: false,
+                cull_mode: Some(Face::Front),
+                ..default()
+            }),
+            ..default()
+        },
+        EnvironmentMarker,
+        NotShadowCaster,
+        NotShadowReceiver,
+        Pickable::IGNORE,
+        Sky,
+    ));
+
+    // Sun, or moon by night
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                shadows_enabled: true,
+                ..default()
+            },
+            cascade_shadow_config: CascadeShadowConfigBuilder {
+                first_cascade_far_bound: 8.0,
+                maximum_distance: 30.0,
+                ..default()
+            }.into(),
+            ..default()
+        },
+        EnvironmentMarker,
+        Sun,
+    ));
+
+    // Sea, its waves are moved every frame
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(shape::Plane { size: SEA_SIZE, subdivisions: 60 }.into()),
+            material: materials.add(StandardMaterial {
+                base_color: SEA_COLOR,
+                alpha_mode: AlphaMode::Blend,
+                perceptual_roughness: 0.2,
+                reflectance: 0.6,
+                ..default()
+            }),
+            transform: Transform::from_xyz(0.0, SEA_LEVEL, 0.0),
+            ..default()
+        },
+        EnvironmentMarker,
+        NotShadowCaster,
+        Pickable::IGNORE,
+        Sea,
+    ));
+
+    // Island, a flattened mound of sand under the base with a few rocks along the shore
+    let sand_material = materials.add(StandardMaterial {
+        base_color: SAND_COLOR,
+        perceptual_roughness: 0.9,
+        ..default()
+    });
+    let rock_material = materials.add(StandardMaterial {
+        base_color: Color::rgb(0.45, 0.43, 0.4),
+        perceptual_roughness: 0.8,
+        ..default()
+    });
+    let mound_mesh = meshes.add(shape::UVSphere { radius: 1.0, sectors: 48, stacks: 24 }.into());
+    commands.spawn((
+        PbrBundle {
+            mesh: mound_mesh.clone(),
+            material: sand_material,
+            transform: Transform::from_xyz(0.0, -1.7, 0.0).with_scale(Vec3::new(7.0, 1.2, 7.0)),
+            ..default()
+        },
+        EnvironmentMarker,
+        Pickable::IGNORE,
+    ));
+    for (angle, size) in [(0.4, 0.5), (1.5, 0.35), (2.3, 0.6), (3.6, 0.4), (4.4, 0.55), (5.5, 0.3)] {
+        let (sin, cos) = f32::sin_cos(angle);
+        commands.spawn((
+            PbrBundle {
+                mesh: mound_mesh.clone(),
+                material: rock_material.clone(),
+                transform: Transform::from_xyz(6.0 * cos, SEA_LEVEL, 6.0 * sin)
+                    .with_rotation(Quat::from_rotation_y(angle))
+                    .with_scale(Vec3::new(size * 1.4, size, size)),
+                ..default()
+            },
+            EnvironmentMarker,
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+// Height and normal of the sea's surface, a few crossing swells
+fn wave(x: f32, z: f32, t: f32) -> (f32, Vec3) {
+    const SWELLS: [(f32, f32, f32, f32); 3] = [
+        // Amplitude, along x, along z and speed
+        (0.06, 0.9, 0.0, 1.3),
+        (0.04, 0.0, 1.3, -1.7),
+        (0.03, 0.5, 0.5, 0.9),
+    ];
+
+    let mut height = 0.0;
+    let mut slope = Vec2::ZERO;
+    for (amplitude, kx, kz, speed) in SWELLS {
+        let phase = kx * x + kz * z + speed * t;
+        height += amplitude * phase.sin();
+        slope += amplitude * phase.cos() * Vec2::new(kx, kz);
+    }
+    (height, Vec3::new(-slope.x, 1.0, -slope.y).normalize())
+}
\ No newline at end of file
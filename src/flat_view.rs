@@ -0,0 +1,332 @@
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use bevy::utils::hashbrown::HashMap;
+use bevy_mod_picking::backend::HitData;
+
+use crate::{
+    AppState,
+    board::{Board, Piece, PickBlocker, PieceMarker, Preview, Turn},
+    camera::BoardCamera,
+    network::ChatInput,
+    theme::Theme,
+};
+
+// The flat view draws the board as a grid of UI cells in place of the 3D scene. Clicks on a cell are
+// passed on as clicks on the top piece of its stack, so they reach the controllers and the editor just
+// like picks in the scene
+
+pub struct FlatViewPlugin;
+impl Plugin for FlatViewPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ViewMode>()
+            .add_systems(Update, (
+                toggle_view,
+                sync_view,
+                apply_deferred,
+                update_cells,
+                forward_clicks,
+            ).chain().run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor))))
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(OnExit(AppState::Editor), cleanup);
+    }
+}
+
+// Constants
+
+const BACKGROUND_COLOR: Color = Color::rgb(0.1, 0.1, 0.12);
+const CELL_SIZE: f32 = 110.0;
+const CLICKABLE_COLOR: Color = Color::YELLOW;
+// Blocks darken towards the ground so that levels can be told apart by shade
+const LEVEL_SHADES: [f32; 3] = [0.72, 0.86, 1.0];
+const TEXT_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
+
+// Resources
+
+#[derive(Clone, Copy, Default, PartialEq, Resource)]
+pub enum ViewMode {
+    Flat,
+    #[default]
+    Scene,
+}
+
+// Components
+
+#[derive(Component)]
+struct FlatCell {
+    row: usize,
+    column: usize,
+}
+
+#[derive(Component)]
+struct FlatFrame;
+
+#[derive(Component)]
+struct FlatLabel {
+    row: usize,
+    column: usize,
+}
+
+#[derive(Component)]
+struct FlatToken {
+    row: usize,
+    column: usize,
+}
+
+#[derive(Component)]
+struct FlatViewMarker;
+
+// Systems
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<FlatViewMarker>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn forward_clicks(
+    mut pointer_down: EventWriter<Pointer<Down>>,
+    blocker_query: Query<(), With<PickBlocker>>,
+    camera_query: Query<Entity, With<BoardCamera>>,
+    cell_query: Query<(&Interaction, &FlatCell), Changed<Interaction>>,
+    pieces_query: Query<(Entity, &PieceMarker, Option<&Pickable>)>,
+    pointer_query: Query<(&PointerId, &PointerLocation)>,
+) {
+    if !blocker_query.is_empty() {
+        return;
+    }
+    let Some(location) = pointer_query
+        .iter()
+        .find_map(|(id, location)| location.location().filter(|_| id.is_mouse()))
+    else {
+        return;
+    };
+
+    for (_, FlatCell { row, column }) in cell_query.iter().filter(|(x, _)| **x == Interaction::Pressed) {
+        // Only the top of a stack can be hit, and only while it's pickable
+        let top = pieces_query
+            .iter()
+            .filter(|(_, x, _)| x.row == *row && x.column == *column)
+            .max_by_key(|(_, x, _)| x.height);
+        let Some((target, _, pickable)) = top else {
+            continue;
+        };
+        if pickable.is_some_and(|x| !x.should_emit_events) {
+            continue;
+        }
+
+        pointer_down.send(Pointer::new(PointerId::Mouse, location.clone(), target, Down {
+            button: PointerButton::Primary,
+            hit: HitData::new(camera_query.single(), 0.0, None, None),
+        }));
+    }
+}
+
+fn sync_view(
+    mut camera_query: Query<&mut Camera, With<BoardCamera>>,
+    mut commands: Commands,
+    flat_query: Query<Entity, With<FlatViewMarker>>,
+    view_mode: Res<ViewMode>,
+) {
+    let flat = *view_mode == ViewMode::Flat;
+
+    // The scene isn't rendered at all under the flat view
+    for mut camera in camera_query.iter_mut() {
+        if camera.is_active == flat {
+            camera.is_active = !flat;
+        }
+    }
+
+    match (flat, flat_query.is_empty()) {
+        (true, true) => spawn_flat_view(&mut commands),
+        (false, false) => {
+            for entity in flat_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn toggle_view(
+    mut view_mode: ResMut<ViewMode>,
+    chat_input: Option<Res<ChatInput>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::V) && !chat_input.is_some_and(|x| x.is_typing()) {
+        *view_mode = match *view_mode {
+            ViewMode::Flat => ViewMode::Scene,
+            ViewMode::Scene => ViewMode::Flat,
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_cells(
+    mut cell_query: Query<(&FlatCell, &mut BackgroundColor, &mut BorderColor), Without<FlatToken>>,
+    mut frame_query: Query<&mut BorderColor, (With<FlatFrame>, Without<FlatCell>)>,
+    mut label_query: Query<(&FlatLabel, &mut Text)>,
+    mut token_query: Query<(&FlatToken, &mut BackgroundColor, &mut BorderColor), (Without<FlatCell>, Without<FlatFrame>)>,
+    board: Res<Board>,
+    pieces_query: Query<(&PieceMarker, Option<&Pickable>)>,
+    preview: Res<Preview>,
+    theme: Res<Theme>,
+) {
+    let palette = theme.palette();
+    // Cells show a previewed position when there is one, and nothing can be clicked meanwhile
+    let board = preview.board.as_ref().unwrap_or(&board);
+
+    let mut tops: HashMap<(usize, usize), (&PieceMarker, bool)> = HashMap::new();
+    for (piece, pickable) in pieces_query.iter() {
+        let clickable = pickable.is_some_and(|x| x.should_emit_events);
+        let top = tops.entry((piece.row, piece.column)).or_insert((piece, clickable));
+        if piece.height > top.0.height {
+            *top = (piece, clickable);
+        }
+    }
+
+    for (FlatCell { row, column }, mut background_color, mut border_color) in cell_query.iter_mut() {
+        let levels = board.get_levels(*row, *column);
+        let color = match levels {
+            0 if (row + column) % 2 == 0 => palette.light_square,
+            0 => palette.dark_square,
+            levels => palette.block * LEVEL_SHADES[levels - 1],
+        };
+        if background_color.0 != color {
+            background_color.0 = color;
+        }
+
+        let clickable = preview.board.is_none() && tops.get(&(*row, *column)).is_some_and(|(_, x)| *x);
+        let color = if clickable { CLICKABLE_COLOR } else { BACKGROUND_COLOR };
+        if border_color.0 != color {
+            border_color.0 = color;
+        }
+    }
+
+    // Silver's workers are outlined as well as coloured, as they are shaped differently in the scene
+    for (FlatToken { row, column }, mut background_color, mut border_color) in token_query.iter_mut() {
+        let (color, outline) = match board.get_piece(*row, *column, board.get_levels(*row, *column) + 1) {
+            Some(Piece::Dome) => (palette.dome, palette.dome),
+            Some(Piece::Worker { turn: Turn::P2 }) => (palette.player2, Color::BLACK),
+            Some(Piece::Worker { .. }) => (palette.player1, palette.player1),
+            _ => (Color::NONE, Color::NONE),
+        };
+        if background_color.0 != color || border_color.0 != outline {
+            background_color.0 = color;
+            border_color.0 = outline;
+        }
+    }
+
+    for (FlatLabel { row, column }, mut text) in label_query.iter_mut() {
+        let levels = board.get_levels(*row, *column);
+        let dome = board.get_piece(*row, *column, levels + 1) == Some(&Piece::Dome);
+        let value = if levels > 0 { levels.to_string() } else { String::new() };
+        let color = if dome { Color::WHITE } else { TEXT_COLOR };
+        if text.sections[0].value != value || text.sections[0].style.color != color {
+            text.sections[0].value = value;
+            text.sections[0].style.color = color;
+        }
+    }
+
+    let color = palette.player(*board.get_turn());
+    for mut border_color in frame_query.iter_mut() {
+        if border_color.0 != color {
+            border_color.0 = color;
+        }
+    }
+}
+
+// Functions
+
+fn spawn_flat_view(commands: &mut Commands) {
+    let label_style = TextStyle {
+        font_size: 32.0,
+        color: TEXT_COLOR,
+        ..default()
+    };
+
+    // The UI needs a camera of its own once the scene's camera is off
+    commands.spawn((Camera2dBundle::default(), FlatViewMarker));
+
+    // The grid is laid out as the scene is first seen, from gold's side
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                z_index: ZIndex::Global(-1),
+                ..default()
+            },
+            FlatViewMarker,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            display: Display::Grid,
+                            grid_template_columns: RepeatedGridTrack::px(5, CELL_SIZE),
+                            grid_template_rows: RepeatedGridTrack::px(5, CELL_SIZE),
+                            border: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    FlatFrame,
+                ))
+                .with_children(|parent| {
+                    for row in 0..5 {
+                        for column in (0..5).rev() {
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            border: UiRect::all(Val::Px(4.0)),
+                                            align_items: AlignItems::Center,
+                                            justify_content: JustifyContent::Center,
+                                            ..default()
+                                        },
+                                        ..default()
+                                    },
+                                    FlatCell { row, column },
+                                ))
+                                .with_children(|parent| {
+                                    parent
+                                        .spawn((
+                                            NodeBundle {
+                                                style: Style {
+                                                    width: Val::Percent(60.0),
+                                                    height: Val::Percent(60.0),
+                                                    border: UiRect::all(Val::Px(5.0)),
+                                                    align_items: AlignItems::Center,
+                                                    justify_content: JustifyContent::Center,
+                                                    ..default()
+                                                },
+                                                ..default()
+                                            },
+                                            FlatToken { row, column },
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn((
+                                                TextBundle::from_section("", label_style.clone())
+                                                    .with_text_alignment(TextAlignment::Center),
+                                                FlatLabel { row, column },
+                                            ));
+                                        });
+                                });
+                        }
+                    }
+                });
+        });
+}
\ No newline at end of file
@@ -0,0 +1,362 @@
+use bevy::prelude::*;
+
+use bevy_mod_picking::backends::raycast::bevy_mod_raycast::prelude::NoBackfaceCulling;
+
+use crate::{
+    AppState,
+    board::{Board, PickBlocker, Preview, Rules, StartingPosition, Turn},
+    network::ChatInput,
+    notation::{format_ply, parse_ply},
+    replay::starting_board,
+    theme::Theme,
+};
+
+// The move history lists the plies of the game so far. Clicking one shows the position it led to in
+// place of the live one, without letting anything be played on it, until the game is returned to
+
+pub struct HistoryPlugin;
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(Update, (
+                toggle_panel,
+                buttons_system,
+                update_panel,
+                block_picks,
+            ).chain().run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), cleanup);
+    }
+}
+
+// Constants
+
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.65);
+const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
+const PANEL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+const SELECTED_BUTTON_COLOR: Color = Color::rgb(0.45, 0.4, 0.05);
+const VISIBLE_PLIES: usize = 14;
+
+// Resources
+
+#[derive(Default, Resource)]
+struct HistoryPanel {
+    collapsed: bool,
+    // Number of plies played to reach the previewed position, none for the live game
+    selected: Option<usize>,
+}
+
+// Components
+
+#[derive(Clone, Copy, Component)]
+enum HistoryButton {
+    Live,
+    Next,
+    Ply(usize),
+    Previous,
+    Toggle,
+}
+
+#[derive(Component)]
+struct HistoryBody;
+
+#[derive(Component)]
+struct HistoryList;
+
+#[derive(Component)]
+struct HistoryMarker;
+
+#[derive(Component)]
+struct HistoryStatus;
+
+#[derive(Component)]
+struct PreviewBlockerMarker;
+
+// Systems
+
+fn block_picks(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    blocker_query: Query<Entity, With<PreviewBlockerMarker>>,
+    preview: Res<Preview>,
+) {
+    match (preview.board.is_some(), blocker_query.get_single()) {
+        (true, Err(_)) => {
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(shape::UVSphere { radius: 9.9, ..default() }.into()),
+                    material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.0).into()),
+                    ..default()
+                },
+                HistoryMarker,
+                NoBackfaceCulling,
+                PickBlocker,
+                PreviewBlockerMarker,
+            ));
+        }
+        (false, Ok(entity)) => {
+            commands.entity(entity).despawn();
+        }
+        _ => {}
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn buttons_system(
+    mut interaction_query: Query<
+        (&Interaction, &HistoryButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut panel: ResMut<HistoryPanel>,
+    board: Res<Board>,
+) {
+    let plies = board.get_history().len();
+    let shown = panel.selected.unwrap_or(plies);
+
+    for (interaction, button, mut color) in &mut interaction_query {
+        *color = match *interaction {
+            Interaction::Pressed => {
+                // Reaching the last ply is the same as going back to the game
+                let selected = match *button {
+                    HistoryButton::Live => plies,
+                    HistoryButton::Next => shown + 1,
+                    HistoryButton::Ply(index) => index + 1,
+                    HistoryButton::Previous => shown.saturating_sub(1),
+                    HistoryButton::Toggle => {
+                        panel.collapsed = !panel.collapsed;
+                        continue;
+                    }
+                };
+                panel.selected = (selected < plies).then_some(selected);
+                continue;
+            }
+            Interaction::Hovered => HOVERED_BUTTON_COLOR.into(),
+            Interaction::None => match *button {
+                HistoryButton::Ply(index) if index + 1 == shown => SELECTED_BUTTON_COLOR.into(),
+                _ => NORMAL_BUTTON_COLOR.into(),
+            },
+        };
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<HistoryMarker>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<HistoryPanel>();
+}
+
+fn setup(
+    mut commands: Commands,
+) {
+    commands.init_resource::<HistoryPanel>();
+
+    let button_style = Style {
+        height: Val::Px(24.0),
+        margin: UiRect::all(Val::Px(2.0)),
+        padding: UiRect::horizontal(Val::Px(8.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 16.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+    let spawn_button = |parent: &mut ChildBuilder, button: HistoryButton, label: &str| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: button_style.clone(),
+                    background_color: NORMAL_BUTTON_COLOR.into(),
+                    ..default()
+                },
+                button,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(label, button_text_style.clone()));
+            });
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(5.0),
+                    top: Val::Px(5.0),
+                    width: Val::Px(240.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(3.0)),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                ..default()
+            },
+            HistoryMarker,
+        ))
+        .with_children(|parent| {
+            spawn_button(parent, HistoryButton::Toggle, "Moves (H)");
+
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    HistoryBody,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        HistoryList,
+                    ));
+                    parent.spawn((
+                        TextBundle::from_section("", TextStyle {
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                            ..default()
+                        })
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(3.0)),
+                                ..default()
+                            }),
+                        HistoryStatus,
+                    ));
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            spawn_button(parent, HistoryButton::Previous, "<");
+                            spawn_button(parent, HistoryButton::Next, ">");
+                            spawn_button(parent, HistoryButton::Live, "Back to game");
+                        });
+                });
+        });
+}
+
+fn toggle_panel(
+    mut panel: ResMut<HistoryPanel>,
+    chat_input: Option<Res<ChatInput>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::H) && !chat_input.is_some_and(|x| x.is_typing()) {
+        panel.collapsed = !panel.collapsed;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_panel(
+    mut body_query: Query<&mut Style, With<HistoryBody>>,
+    mut commands: Commands,
+    mut preview: ResMut<Preview>,
+    mut shown: Local<Option<(usize, Option<usize>, bool)>>,
+    mut status_query: Query<&mut Text, With<HistoryStatus>>,
+    board: Res<Board>,
+    list_query: Query<Entity, With<HistoryList>>,
+    panel: Res<HistoryPanel>,
+    rules: Res<Rules>,
+    starting_position: Option<Res<StartingPosition>>,
+    theme: Res<Theme>,
+) {
+    let history = board.get_history();
+    let key = (history.len(), panel.selected, panel.collapsed);
+    if *shown == Some(key) {
+        return;
+    }
+    *shown = Some(key);
+
+    for mut style in body_query.iter_mut() {
+        style.display = if panel.collapsed { Display::None } else { Display::Flex };
+    }
+
+    // The positions are played again from the start, as far as they still agree with the rules
+    let mut positions = vec![starting_board(&board, &rules, starting_position.as_deref())];
+    for ply in history {
+        let mut position = positions[positions.len() - 1].clone();
+        if parse_ply(&position, &format_ply(ply)) != Ok(*ply) {
+            break;
+        }
+        position.apply(ply);
+        positions.push(position);
+    }
+
+    let previewed = panel.selected.and_then(|x| positions.get(x).cloned());
+    if previewed.is_some() || preview.board.is_some() {
+        preview.board = previewed;
+    }
+
+    let current = panel.selected.unwrap_or(history.len());
+    for mut text in status_query.iter_mut() {
+        text.sections[0].value = match panel.selected {
+            Some(selected) => format!("Viewing ply {} of {}, read-only", selected, history.len()),
+            None => "Live game".to_string(),
+        };
+    }
+
+    // A window of plies is listed, following the current one
+    let end = (current + VISIBLE_PLIES / 2).clamp(VISIBLE_PLIES.min(history.len()), history.len());
+    let start = end.saturating_sub(VISIBLE_PLIES);
+    let palette = theme.palette();
+    for entity in list_query.iter() {
+        commands.entity(entity).despawn_descendants().with_children(|parent| {
+            for (index, ply) in history.iter().enumerate().take(end).skip(start) {
+                let turn = positions.get(index).map(|x| *x.get_turn());
+                let player = match turn {
+                    Some(Turn::P1) => "Gold",
+                    Some(Turn::P2) => "Silver",
+                    _ => "",
+                };
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                height: Val::Px(22.0),
+                                margin: UiRect::all(Val::Px(1.0)),
+                                padding: UiRect::horizontal(Val::Px(6.0)),
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: if index + 1 == current {
+                                SELECTED_BUTTON_COLOR.into()
+                            } else {
+                                NORMAL_BUTTON_COLOR.into()
+                            },
+                            ..default()
+                        },
+                        HistoryButton::Ply(index),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            format!("{}. {} {}", index + 1, player, format_ply(ply)),
+                            TextStyle {
+                                font_size: 16.0,
+                                color: turn.map_or(Color::WHITE, |x| palette.player(x)),
+                                ..default()
+                            },
+                        ));
+                    });
+            }
+        });
+    }
+}
\ No newline at end of file
@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+
+use bevy::{
+    ui::UiSystem,
+    utils::hashbrown::HashMap,
+};
+use itertools::Itertools;
+
+use crate::{
+    AppState,
+    board::{Board, PieceMarker, Preview, PreviewMarker},
+    camera::BoardCamera,
+    network::ChatInput,
+    notation::format_square,
+};
+
+pub struct LabelsPlugin;
+impl Plugin for LabelsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<LabelSettings>()
+            .add_systems(Update, (
+                toggle_labels,
+                update_labels,
+            ).chain().run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor))))
+            .add_systems(PostUpdate,
+                place_labels.before(UiSystem::Layout).run_if(in_state(AppState::InGame).or_else(in_state(AppState::Editor)))
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup)
+            .add_systems(OnExit(AppState::Editor), cleanup);
+    }
+}
+
+// Constants
+
+const COORDINATE_DISTANCE: f32 = 3.2;
+const HEIGHT_OFFSET: f32 = 1.2;
+
+// Resources
+
+#[derive(Default, Resource)]
+pub struct LabelSettings {
+    pub visible: bool,
+}
+
+// Components
+
+#[derive(Component)]
+enum BoardLabel {
+    // Coordinates run along every side of the base so that some of them are always in view
+    Coordinate(Vec3),
+    // Floats over the top of the tower on the square
+    Height {
+        row: usize,
+        column: usize,
+    },
+}
+
+// Systems
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<BoardLabel>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn place_labels(
+    mut label_query: Query<(&BoardLabel, &Node, &mut Style, &mut Visibility)>,
+    camera_query: Query<(&Camera, &Transform), With<BoardCamera>>,
+    pieces_query: Query<(&PieceMarker, &Transform)>,
+    preview: Res<Preview>,
+    preview_query: Query<(&PreviewMarker, &Transform)>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    // The camera has no parent, its transform this frame is where it will be drawn from
+    let camera_transform = GlobalTransform::from(*camera_transform);
+
+    // A previewed position stands in for the pieces on the board
+    let pieces: Vec<(&PieceMarker, &Transform)> = if preview.board.is_some() {
+        preview_query.iter().map(|(x, y)| (&x.0, y)).collect()
+    } else {
+        pieces_query.iter().collect()
+    };
+    let mut tops: HashMap<(usize, usize), (usize, Vec3)> = HashMap::new();
+    for (piece, transform) in pieces {
+        let top = tops.entry((piece.row, piece.column)).or_insert((piece.height, transform.translation));
+        if piece.height >= top.0 {
+            *top = (piece.height, transform.translation);
+        }
+    }
+
+    for (label, node, mut style, mut visibility) in label_query.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+
+        let position = match *label {
+            BoardLabel::Coordinate(position) => position,
+            BoardLabel::Height { row, column } => match tops.get(&(row, column)) {
+                Some((_, translation)) => *translation + Vec3::Y * HEIGHT_OFFSET,
+                None => continue,
+            },
+        };
+        let Some(viewport_position) = camera.world_to_viewport(&camera_transform, position) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let corner = viewport_position - node.size() / 2.0;
+        style.left = Val::Px(corner.x);
+        style.top = Val::Px(corner.y);
+    }
+}
+
+fn toggle_labels(
+    mut settings: ResMut<LabelSettings>,
+    chat_input: Option<Res<ChatInput>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::L) && !chat_input.is_some_and(|x| x.is_typing()) {
+        settings.visible = !settings.visible;
+    }
+}
+
+fn update_labels(
+    mut commands: Commands,
+    mut label_query: Query<(&BoardLabel, &mut Text, &mut Visibility)>,
+    board: Res<Board>,
+    camera_query: Query<&Camera, With<BoardCamera>>,
+    preview: Res<Preview>,
+    settings: Res<LabelSettings>,
+) {
+    if label_query.is_empty() {
+        spawn_labels(&mut commands);
+        return;
+    }
+
+    let board = preview.board.as_ref().unwrap_or(&board);
+    // Labels only belong to the 3D scene
+    let shown = settings.visible && camera_query.get_single().is_ok_and(|x| x.is_active);
+
+    for (label, mut text, mut visibility) in label_query.iter_mut() {
+        let value = match *label {
+            BoardLabel::Coordinate(_) => None,
+            BoardLabel::Height { row, column } => Some(board.get_levels(row, column)),
+        };
+        if let Some(levels) = value {
+            let levels = levels.to_string();
+            if text.sections[0].value != levels {
+                text.sections[0].value = levels;
+            }
+        }
+
+        let target = if shown && value != Some(0) { Visibility::Inherited } else { Visibility::Hidden };
+        if *visibility != target {
+            *visibility = target;
+        }
+    }
+}
+
+// Functions
+
+fn spawn_labels(commands: &mut Commands) {
+    let coordinate_style = TextStyle {
+        font_size: 24.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+    let height_style = TextStyle {
+        font_size: 28.0,
+        color: Color::YELLOW,
+        ..default()
+    };
+    let spawn = |commands: &mut Commands, value: String, style: TextStyle, label: BoardLabel| {
+        commands.spawn((
+            TextBundle::from_section(value, style)
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    padding: UiRect::axes(Val::Px(4.0), Val::Px(0.0)),
+                    ..default()
+                })
+                .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.5)),
+            label,
+            ZIndex::Global(-1),
+        ));
+    };
+
+    // Squares sit at x = row - 2 and z = column - 2, so rows are named along z and columns along x
+    for i in 0..5 {
+        let offset = i as f32 - 2.0;
+        let column = format_square(0, i)[..1].to_string();
+        let row = format_square(i, 0)[1..].to_string();
+        for side in [-COORDINATE_DISTANCE, COORDINATE_DISTANCE] {
+            spawn(commands, column.clone(), coordinate_style.clone(), BoardLabel::Coordinate(Vec3::new(side, 0.0, offset)));
+            spawn(commands, row.clone(), coordinate_style.clone(), BoardLabel::Coordinate(Vec3::new(offset, 0.0, side)));
+        }
+    }
+
+    for (row, column) in (0..5).cartesian_product(0..5) {
+        spawn(commands, String::new(), height_style.clone(), BoardLabel::Height { row, column });
+    }
+}
\ No newline at end of file
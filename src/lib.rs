@@ -0,0 +1,109 @@
+mod animation;
+mod board;
+mod camera;
+mod clock;
+mod controller;
+mod editor;
+mod engine_protocol;
+mod environment;
+mod flat_view;
+mod history;
+mod labels;
+mod menus;
+pub mod network;
+mod notation;
+mod puzzle;
+mod replay;
+mod settings;
+mod statistics;
+mod theme;
+
+use bevy::prelude::*;
+use bevy_mod_picking::prelude::*;
+
+use bevy::window::PresentMode;
+
+pub fn run() {
+    // "--engine" runs the AI as a text protocol engine instead of the game,
+    // "--bot <command>" lets games be played against such an engine
+    // and "--server [port]" runs a headless server pairing players on the local network, like the server binary
+    let arguments: Vec<String> = std::env::args().collect();
+    if arguments.iter().any(|x| x == "--engine") {
+        engine_protocol::run();
+        return;
+    }
+    if let Some(i) = arguments.iter().position(|x| x == "--server") {
+        let port = arguments.get(i + 1).and_then(|x| x.parse().ok()).unwrap_or(network::DEFAULT_PORT);
+        network::server::run(port);
+        return;
+    }
+
+    let mut app = App::new();
+    if let Some(i) = arguments.iter().position(|x| x == "--bot") {
+        let command = arguments.get(i + 1).expect("--bot needs a command").clone();
+        app.insert_resource(controller::ExternalBot { command });
+    }
+
+    app
+        .insert_resource(ClearColor(Color::BLACK))
+        .add_plugins(DefaultPlugins
+            .set(
+                WindowPlugin {
+                    primary_window: Some(Window {
+                        present_mode: PresentMode::AutoVsync,
+                        resolution: (1270., 720.).into(),
+                        title: "Santorini".to_string(),
+                        ..default()
+                    }),
+                    ..default()
+                }
+            )
+        )
+        .add_plugins(DefaultPickingPlugins)
+        .add_state::<AppState>()
+        .add_systems(PostStartup, picking_setup)
+        .add_plugins((
+            animation::AnimationPlugin,
+            board::BoardPlugin,
+            camera::CameraPlugin,
+            clock::ClockPlugin,
+            controller::ControllersPlugin,
+            editor::EditorPlugin,
+            environment::EnvironmentPlugin,
+            flat_view::FlatViewPlugin,
+            history::HistoryPlugin,
+            labels::LabelsPlugin,
+            menus::MenusPlugin,
+            network::NetworkPlugin,
+            puzzle::PuzzlePlugin,
+            settings::SettingsPlugin,
+            statistics::StatisticsPlugin,
+        ))
+        .run();
+}
+
+// States
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, States)]
+pub enum AppState {
+    #[default]
+    Menu,
+    Editor,
+    InGame,
+    Lobby,
+    PuzzleMenu,
+    Reset,
+    Settings,
+}
+
+// Setup
+
+fn picking_setup(
+    mut global_highlight: ResMut<GlobalHighlight<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    *global_highlight = GlobalHighlight {
+        hovered: materials.add(Color::rgb(0.25, 0.65, 0.25).into()),
+        pressed: materials.add(Color::rgb(0.35, 0.75, 0.35).into()),
+    };
+}
@@ -0,0 +1,276 @@
+use bevy::prelude::*;
+
+use bevy::time::Stopwatch;
+
+use crate::{
+    AppState,
+    animation::Tween,
+    board::{Board, Ply, Rules, StartingPosition, Turn, WinReason},
+    controller::Controllers,
+    network::NetworkSession,
+    puzzle::Puzzle,
+    replay::{save_replay, starting_board},
+};
+
+pub struct GameOverMenuPlugin;
+impl Plugin for GameOverMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame), setup)
+            .add_systems(Update, (
+                buttons_system,
+                show_menu,
+            ).run_if(in_state(AppState::InGame)))
+            .add_systems(OnExit(AppState::InGame), cleanup);
+    }
+}
+
+// Constants
+
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.65);
+const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
+
+// Resources
+
+#[derive(Default, Resource)]
+struct GameOver {
+    // Only runs while the game is in progress
+    duration: Stopwatch,
+}
+
+// Components
+
+#[derive(Component)]
+enum GameOverMenuButton {
+    Rematch,
+    Analyze,
+    SaveReplay,
+    MainMenu,
+}
+
+#[derive(Component)]
+struct GameOverMenuMarker;
+
+#[derive(Component)]
+struct StatusText;
+
+// Systems
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn buttons_system(
+    mut commands: Commands,
+    mut interaction_query: Query<
+        (&Interaction, &GameOverMenuButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut status_query: Query<&mut Text, With<StatusText>>,
+    board: Res<Board>,
+    controllers: Res<Controllers>,
+    game_over: Res<GameOver>,
+    network_session: Option<Res<NetworkSession>>,
+    puzzle: Option<Res<Puzzle>>,
+    rules: Res<Rules>,
+    starting_position: Option<Res<StartingPosition>>,
+) {
+    for (interaction, button, mut color) in &mut interaction_query {
+        *color = match *interaction {
+            Interaction::Pressed => {
+                match *button {
+                    // Puzzles are retried from the same side
+                    GameOverMenuButton::Rematch => {
+                        if puzzle.is_none() {
+                            commands.insert_resource(Controllers { p1: controllers.p2, p2: controllers.p1 });
+                        }
+                        next_state.set(AppState::Reset);
+                    }
+                    GameOverMenuButton::Analyze => {
+                        commands.remove_resource::<Puzzle>();
+                        commands.insert_resource(StartingPosition { board: board.analysis() });
+                        next_state.set(AppState::Editor);
+                    }
+                    GameOverMenuButton::SaveReplay => {
+                        let start = starting_board(&board, &rules, starting_position.as_deref());
+                        let chat = network_session.as_ref().map_or(&[][..], |x| x.get_chat());
+
+                        let status = match save_replay(&start, &board, game_over.duration.elapsed(), chat) {
+                            Ok(path) => format!("Saved {:?}", path.file_name().unwrap_or_default()),
+                            Err(error) => format!("Couldn't save: {}", error),
+                        };
+                        for mut text in status_query.iter_mut() {
+                            text.sections[0].value = status.clone();
+                        }
+                    }
+                    GameOverMenuButton::MainMenu => next_state.set(AppState::Menu),
+                }
+                continue;
+            }
+            Interaction::Hovered => HOVERED_BUTTON_COLOR.into(),
+            Interaction::None => NORMAL_BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<GameOverMenuMarker>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<GameOver>();
+}
+
+fn setup(
+    mut commands: Commands,
+) {
+    commands.init_resource::<GameOver>();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn show_menu(
+    mut commands: Commands,
+    mut game_over: ResMut<GameOver>,
+    board: Res<Board>,
+    menu_query: Query<(), With<GameOverMenuMarker>>,
+    network_session: Option<Res<NetworkSession>>,
+    puzzle: Option<Res<Puzzle>>,
+    time: Res<Time>,
+    tween_query: Query<(), With<Tween>>,
+) {
+    if matches!(board.get_turn(), Turn::P1 | Turn::P2) {
+        game_over.duration.tick(time.delta());
+        return;
+    }
+    // The last ply gets to play out before the menu covers the board
+    if !menu_query.is_empty() || !tween_query.is_empty() {
+        return;
+    }
+
+    const BASE_COLOR: Color = Color::rgba(0.97, 0.97, 1.00, 0.8);
+
+    let title = match board.get_turn() {
+        Turn::WinP1 => "Gold wins!",
+        Turn::WinP2 => "Silver wins!",
+        _ => "Draw!",
+    };
+    let reason = match board.get_result().map(|x| x.reason) {
+        Some(WinReason::Agreement) => "by agreement",
+        Some(WinReason::Blocked) => "by blocking",
+        Some(WinReason::Disconnect) => "by disconnection",
+        Some(WinReason::Forfeit) => "by forfeit",
+        Some(WinReason::Resignation) => "by resignation",
+        Some(WinReason::Timeout) => "on time",
+        Some(WinReason::Tower) => "by climbing",
+        None => "",
+    };
+    let moves = board.get_history().iter().filter(|x| matches!(x, Ply::Action(_))).count();
+    let seconds = game_over.duration.elapsed().as_secs();
+    let summary = format!("{}\n{} moves in {}:{:02}", reason, moves, seconds / 60, seconds % 60);
+
+    // Network games can't be restarted by one side alone
+    let mut buttons = vec![
+        (GameOverMenuButton::Analyze, "Analyze"),
+        (GameOverMenuButton::SaveReplay, "Save Replay"),
+        (GameOverMenuButton::MainMenu, "Main Menu"),
+    ];
+    if network_session.is_none() {
+        buttons.insert(0, (GameOverMenuButton::Rematch, if puzzle.is_some() { "Retry" } else { "Rematch" }));
+    }
+
+    let button_style = Style {
+        width: Val::Px(240.0),
+        height: Val::Px(50.0),
+        margin: UiRect::all(Val::Px(6.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 32.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+    let text_style = TextStyle {
+        font_size: 28.0,
+        color: Color::rgb(0.05, 0.05, 0.25),
+        ..default()
+    };
+    let title_style = TextStyle {
+        font_size: 60.0,
+        color: Color::rgb(0.05, 0.05, 0.65),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            GameOverMenuMarker,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: BASE_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(title, title_style)
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(10.0)),
+                                ..default()
+                            }),
+                    );
+
+                    parent.spawn(
+                        TextBundle::from_section(summary, text_style.clone())
+                            .with_text_alignment(TextAlignment::Center)
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(6.0)),
+                                ..default()
+                            }),
+                    );
+
+                    for (button, label) in buttons {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                button,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(label, button_text_style.clone()));
+                            });
+                    }
+
+                    parent.spawn((
+                        TextBundle::from_section("", text_style)
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(6.0)),
+                                ..default()
+                            }),
+                        StatusText,
+                    ));
+                });
+        });
+}
\ No newline at end of file
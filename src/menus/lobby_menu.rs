@@ -0,0 +1,345 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppState,
+    network::{DEFAULT_PORT, LastSession, NetworkLobby},
+};
+
+pub struct LobbyMenuPlugin;
+impl Plugin for LobbyMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::Lobby), setup)
+            .add_systems(Update, (
+                buttons_system,
+                type_address,
+                update_games,
+                update_texts,
+            ).run_if(in_state(AppState::Lobby)))
+            .add_systems(OnExit(AppState::Lobby), cleanup);
+    }
+}
+
+// Constants
+
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.65);
+const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
+
+// Resources
+
+#[derive(Resource)]
+struct LobbyAddress {
+    value: String,
+}
+
+// Components
+
+#[derive(Component)]
+struct AddressText;
+
+#[derive(Component)]
+struct GameList;
+
+#[derive(Component)]
+enum LobbyMenuButton {
+    Back,
+    Connect,
+    Game(usize),
+    List,
+    Resume,
+    Watch,
+}
+
+#[derive(Component)]
+struct LobbyMenuMarker;
+
+#[derive(Component)]
+struct StatusText;
+
+// Systems
+
+#[allow(clippy::type_complexity)]
+fn buttons_system(
+    mut commands: Commands,
+    mut interaction_query: Query<
+        (&Interaction, &LobbyMenuButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut lobby: Option<ResMut<NetworkLobby>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    address: Option<Res<LobbyAddress>>,
+    last_session: Option<Res<LastSession>>,
+) {
+    for (interaction, button, mut color) in &mut interaction_query {
+        *color = match *interaction {
+            Interaction::Pressed => {
+                match *button {
+                    LobbyMenuButton::Back => next_state.set(AppState::Menu),
+                    LobbyMenuButton::Connect => if let Some(address) = address.as_ref() {
+                        commands.insert_resource(NetworkLobby::join(&address.value));
+                    },
+                    LobbyMenuButton::Game(id) => if let Some(lobby) = lobby.as_mut() {
+                        lobby.join_game(id);
+                    },
+                    LobbyMenuButton::List => if let Some(address) = address.as_ref() {
+                        commands.insert_resource(NetworkLobby::list(&address.value));
+                    },
+                    LobbyMenuButton::Resume => if let Some(last_session) = last_session.as_ref() {
+                        commands.insert_resource(NetworkLobby::resume(last_session));
+                    },
+                    LobbyMenuButton::Watch => if let Some(address) = address.as_ref() {
+                        commands.insert_resource(NetworkLobby::watch(&address.value));
+                    },
+                }
+                continue;
+            }
+            Interaction::Hovered => HOVERED_BUTTON_COLOR.into(),
+            Interaction::None => NORMAL_BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<LobbyMenuMarker>>,
+) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<LobbyAddress>();
+}
+
+fn setup(
+    mut commands: Commands,
+    last_session: Option<Res<LastSession>>,
+    lobby: Option<Res<NetworkLobby>>,
+) {
+    commands.spawn((LobbyMenuMarker, Camera2dBundle::default()));
+
+    // Hosts start listening from the main menu, joining players first type an address
+    let joining = lobby.is_none();
+    if joining {
+        commands.insert_resource(LobbyAddress { value: format!("127.0.0.1:{}", DEFAULT_PORT) });
+    }
+
+    const BASE_COLOR: Color = Color::rgb(0.97, 0.97, 1.00);
+
+    let button_style = Style {
+        width: Val::Px(320.0),
+        height: Val::Px(50.0),
+        margin: UiRect::all(Val::Px(6.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 32.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+    let text_style = TextStyle {
+        font_size: 28.0,
+        color: Color::rgb(0.05, 0.05, 0.25),
+        ..default()
+    };
+    let title_style = TextStyle {
+        font_size: 60.0,
+        color: Color::rgb(0.05, 0.05, 0.65),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LobbyMenuMarker,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: BASE_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(
+                            TextBundle::from_section(
+                                if joining { "Join game" } else { "Host game" },
+                                title_style,
+                            )
+                                .with_style(Style {
+                                    margin: UiRect::all(Val::Px(15.0)),
+                                    ..default()
+                                }),
+                        );
+
+                    if joining {
+                        parent.spawn((
+                            TextBundle::from_section("", text_style.clone())
+                                .with_style(Style {
+                                    margin: UiRect::all(Val::Px(6.0)),
+                                    ..default()
+                                }),
+                            AddressText,
+                        ));
+                    }
+
+                    parent.spawn((
+                        TextBundle::from_section("", text_style.clone())
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(6.0)),
+                                ..default()
+                            }),
+                        StatusText,
+                    ));
+
+                    if joining {
+                        parent.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    max_width: Val::Px(320.0),
+                                    flex_wrap: FlexWrap::Wrap,
+                                    justify_content: JustifyContent::Center,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            GameList,
+                        ));
+                    }
+
+                    let buttons = if joining {
+                        let mut buttons = vec![
+                            (LobbyMenuButton::Connect, "Connect"),
+                            (LobbyMenuButton::List, "Find games"),
+                            (LobbyMenuButton::Watch, "Watch"),
+                            (LobbyMenuButton::Back, "Back"),
+                        ];
+                        // Players who lost their connection can get back into their last game
+                        if last_session.is_some() {
+                            buttons.insert(2, (LobbyMenuButton::Resume, "Resume last game"));
+                        }
+                        buttons
+                    } else {
+                        vec![(LobbyMenuButton::Back, "Back")]
+                    };
+                    for (button, label) in buttons {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                button,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(label, button_text_style.clone()));
+                            });
+                    }
+                });
+        });
+}
+
+fn type_address(
+    mut commands: Commands,
+    mut ev_character: EventReader<ReceivedCharacter>,
+    address: Option<ResMut<LobbyAddress>>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let Some(mut address) = address else {
+        ev_character.clear();
+        return;
+    };
+
+    for ReceivedCharacter { window: _, char } in ev_character.read() {
+        if char.is_ascii_graphic() {
+            address.value.push(*char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        address.value.pop();
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        commands.insert_resource(NetworkLobby::join(&address.value));
+    }
+}
+
+// The listed games are joined by clicking them
+fn update_games(
+    mut commands: Commands,
+    mut shown: Local<Option<Vec<usize>>>,
+    list_query: Query<Entity, With<GameList>>,
+    lobby: Option<Res<NetworkLobby>>,
+) {
+    let games = lobby.and_then(|x| x.games.clone());
+    if *shown == games {
+        return;
+    }
+    *shown = games.clone();
+
+    for entity in list_query.iter() {
+        commands.entity(entity).despawn_descendants().with_children(|parent| {
+            for id in games.iter().flatten() {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                height: Val::Px(36.0),
+                                margin: UiRect::all(Val::Px(4.0)),
+                                padding: UiRect::horizontal(Val::Px(10.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: NORMAL_BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        LobbyMenuButton::Game(*id),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(format!("Game {}", id), TextStyle {
+                            font_size: 24.0,
+                            color: Color::rgb(0.95, 0.95, 0.95),
+                            ..default()
+                        }));
+                    });
+            }
+        });
+    }
+}
+
+fn update_texts(
+    mut address_query: Query<&mut Text, (With<AddressText>, Without<StatusText>)>,
+    mut status_query: Query<&mut Text, (With<StatusText>, Without<AddressText>)>,
+    address: Option<Res<LobbyAddress>>,
+    lobby: Option<Res<NetworkLobby>>,
+) {
+    if let Some(address) = address {
+        for mut text in address_query.iter_mut() {
+            text.sections[0].value = format!("Address: {}_", address.value);
+        }
+    }
+
+    let status = lobby.map(|x| x.status.clone()).unwrap_or_default();
+    for mut text in status_query.iter_mut() {
+        if text.sections[0].value != status {
+            text.sections[0].value = status.clone();
+        }
+    }
+}
\ No newline at end of file
@@ -4,30 +4,65 @@ use bevy::app::AppExit;
 
 use crate::{
     AppState,
-    controller::{Controllers, Controller}
+    board::{Board, FirstPlayer, PlacementOrder, Rules, StartingPosition, TimeControl},
+    controller::{Controllers, Controller, ExternalBot},
+    network::{DEFAULT_PORT, NetworkLobby},
+    puzzle::Puzzle,
 };
 
 pub struct MainMenuPlugin;
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app
+            .insert_resource(Opponent { controller: Controller::Human })
             .add_systems(OnEnter(AppState::Menu), setup)
-            .add_systems(Update, buttons_system.run_if(in_state(AppState::Menu)))
+            .add_systems(Update, (
+                buttons_system,
+                update_texts,
+            ).chain().run_if(in_state(AppState::Menu)))
             .add_systems(OnExit(AppState::Menu), cleanup);
     }
 }
 
 // Constants
 
+// Clock settings cycle through these, in seconds
+const BASE_TIMES: [u64; 7] = [60, 180, 300, 600, 900, 1800, 3600];
+const BYO_YOMI_PERIODS: [u32; 5] = [1, 2, 3, 5, 10];
+const BYO_YOMI_TIMES: [u64; 5] = [10, 20, 30, 60, 120];
+const ENGINE_DEPTH: usize = 3;
+const FISCHER_INCREMENTS: [u64; 6] = [0, 2, 3, 5, 10, 30];
 const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.65);
 const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
 
+// Resources
+
+#[derive(Resource)]
+struct Opponent {
+    controller: Controller,
+}
+
 // Components
 
-#[derive(Component)]
+#[derive(Clone, Copy, Component)]
 enum MainMenuButton {
+    Clock,
+    ClockBase,
+    // The increment of a Fischer clock, or the length of byo-yomi periods
+    ClockIncrement,
+    ClockPeriods,
+    Domes,
+    Editor,
+    FirstPlayer,
+    Host,
+    Join,
+    Opponent,
+    Placement,
     Play,
+    Puzzles,
     Quit,
+    Settings,
+    Supply,
 }
 
 #[derive(Component)]
@@ -36,8 +71,12 @@ struct MainMenuCamera;
 #[derive(Component)]
 struct MainMenuMarker;
 
+#[derive(Component)]
+struct ClockSettings;
+
 // Systems
 
+#[allow(clippy::type_complexity)]
 fn buttons_system(
     mut commands: Commands,
     mut exit: EventWriter<AppExit>,
@@ -46,6 +85,9 @@ fn buttons_system(
         (Changed<Interaction>, With<Button>),
     >,
     mut next_state: ResMut<NextState<AppState>>,
+    mut opponent: ResMut<Opponent>,
+    mut rules: ResMut<Rules>,
+    external_bot: Option<Res<ExternalBot>>,
 ) {
     for (interaction, button, mut color) in &mut interaction_query {
         *color = match *interaction {
@@ -54,11 +96,70 @@ fn buttons_system(
                     MainMenuButton::Play => {
                         commands.insert_resource(Controllers {
                             p1: Controller::Human,
-                            p2: Controller::Human,
+                            p2: opponent.controller,
                         });
+                        commands.remove_resource::<Puzzle>();
+                        commands.remove_resource::<StartingPosition>();
                         next_state.set(AppState::InGame);
                     }
+                    MainMenuButton::Host => {
+                        commands.insert_resource(NetworkLobby::host(DEFAULT_PORT));
+                        next_state.set(AppState::Lobby);
+                    }
+                    MainMenuButton::Join => next_state.set(AppState::Lobby),
+                    MainMenuButton::Puzzles => next_state.set(AppState::PuzzleMenu),
+                    MainMenuButton::Editor => {
+                        commands.remove_resource::<Puzzle>();
+                        commands.insert_resource(StartingPosition { board: Board::default() });
+                        next_state.set(AppState::Editor);
+                    }
                     MainMenuButton::Quit => exit.send(AppExit),
+                    MainMenuButton::Settings => next_state.set(AppState::Settings),
+                    // Switching clocks keeps the base time
+                    MainMenuButton::Clock => {
+                        rules.time_control = match rules.time_control {
+                            TimeControl::Unlimited => TimeControl::Fischer { base: 300, increment: 3 },
+                            TimeControl::Fischer { base, .. } => TimeControl::ByoYomi { base, period: 30, periods: 3 },
+                            TimeControl::ByoYomi { .. } => TimeControl::Unlimited,
+                        };
+                    }
+                    MainMenuButton::ClockBase => match &mut rules.time_control {
+                        TimeControl::Fischer { base, .. } | TimeControl::ByoYomi { base, .. } => {
+                            *base = next_value(&BASE_TIMES, *base);
+                        }
+                        TimeControl::Unlimited => {}
+                    },
+                    MainMenuButton::ClockIncrement => match &mut rules.time_control {
+                        TimeControl::Fischer { increment, .. } => *increment = next_value(&FISCHER_INCREMENTS, *increment),
+                        TimeControl::ByoYomi { period, .. } => *period = next_value(&BYO_YOMI_TIMES, *period),
+                        TimeControl::Unlimited => {}
+                    },
+                    MainMenuButton::ClockPeriods => if let TimeControl::ByoYomi { periods, .. } = &mut rules.time_control {
+                        *periods = next_value(&BYO_YOMI_PERIODS, *periods);
+                    },
+                    MainMenuButton::Domes => rules.domes_anywhere = !rules.domes_anywhere,
+                    MainMenuButton::FirstPlayer => {
+                        rules.first_player = match rules.first_player {
+                            FirstPlayer::Gold => FirstPlayer::Silver,
+                            FirstPlayer::Silver => FirstPlayer::Random,
+                            FirstPlayer::Random => FirstPlayer::Gold,
+                        };
+                    }
+                    MainMenuButton::Opponent => {
+                        opponent.controller = match opponent.controller {
+                            Controller::Human => Controller::Engine { depth: ENGINE_DEPTH },
+                            Controller::Engine { .. } if external_bot.is_some() => Controller::External,
+                            _ => Controller::Human,
+                        };
+                    }
+                    MainMenuButton::Placement => {
+                        rules.placement_order = match rules.placement_order {
+                            PlacementOrder::Standard => PlacementOrder::Alternating,
+                            PlacementOrder::Alternating => PlacementOrder::StarterLast,
+                            PlacementOrder::StarterLast => PlacementOrder::Standard,
+                        };
+                    }
+                    MainMenuButton::Supply => rules.limited_supply = !rules.limited_supply,
                 }
                 continue;
             }
@@ -79,21 +180,23 @@ fn cleanup(
 
 fn setup(
     mut commands: Commands,
+    opponent: Res<Opponent>,
+    rules: Res<Rules>,
 ) {
     commands.spawn((MainMenuCamera, MainMenuMarker, Camera2dBundle::default()));
 
     const BASE_COLOR: Color = Color::rgb(0.97, 0.97, 1.00);
 
     let button_style = Style {
-        width: Val::Px(250.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(10.0)),
+        width: Val::Px(320.0),
+        height: Val::Px(36.0),
+        margin: UiRect::all(Val::Px(3.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
     };
     let button_text_style = TextStyle {
-        font_size: 40.0,
+        font_size: 30.0,
         color: Color::rgb(0.95, 0.95, 0.95),
         ..default()
     };
@@ -141,36 +244,163 @@ fn setup(
                                 }),
                         );
 
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON_COLOR.into(),
-                                ..default()
-                            },
-                            MainMenuButton::Play,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Play",
-                                button_text_style.clone(),
-                            ));
-                        });
-                    parent
-                        .spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON_COLOR.into(),
-                                ..default()
-                            },
-                            MainMenuButton::Quit,
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Quit",
-                                button_text_style.clone(),
-                            ));
-                        });
+                    for button in [
+                        MainMenuButton::Play,
+                        MainMenuButton::Opponent,
+                        MainMenuButton::Host,
+                        MainMenuButton::Join,
+                        MainMenuButton::Puzzles,
+                        MainMenuButton::Editor,
+                        MainMenuButton::Supply,
+                        MainMenuButton::Domes,
+                        MainMenuButton::Placement,
+                        MainMenuButton::FirstPlayer,
+                        MainMenuButton::Clock,
+                        MainMenuButton::Settings,
+                        MainMenuButton::Quit,
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                button,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    button_text(&button, &opponent, &rules),
+                                    button_text_style.clone(),
+                                ));
+                            });
+
+                        if let MainMenuButton::Clock = button {
+                            parent
+                                .spawn((NodeBundle::default(), ClockSettings))
+                                .with_children(|parent| {
+                                    for button in [
+                                        MainMenuButton::ClockBase,
+                                        MainMenuButton::ClockIncrement,
+                                        MainMenuButton::ClockPeriods,
+                                    ] {
+                                        parent
+                                            .spawn((
+                                                ButtonBundle {
+                                                    style: Style {
+                                                        width: Val::Px(102.0),
+                                                        ..button_style.clone()
+                                                    },
+                                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                                    ..default()
+                                                },
+                                                button,
+                                            ))
+                                            .with_children(|parent| {
+                                                parent.spawn(TextBundle::from_section(
+                                                    button_text(&button, &opponent, &rules),
+                                                    TextStyle {
+                                                        font_size: 20.0,
+                                                        ..button_text_style.clone()
+                                                    },
+                                                ));
+                                            });
+                                    }
+                                });
+                        }
+                    }
                 });
         });
+}
+
+// Labels follow the rules, and the clock settings only show the values that the clock uses
+fn update_texts(
+    mut button_query: Query<(&MainMenuButton, &Children, &mut Style)>,
+    mut settings_query: Query<&mut Style, (With<ClockSettings>, Without<MainMenuButton>)>,
+    mut text_query: Query<&mut Text>,
+    opponent: Res<Opponent>,
+    rules: Res<Rules>,
+) {
+    if !opponent.is_changed() && !rules.is_changed() {
+        return;
+    }
+
+    for (button, children, mut style) in button_query.iter_mut() {
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            text.sections[0].value = button_text(button, &opponent, &rules);
+        }
+        if let MainMenuButton::ClockPeriods = button {
+            style.display = if let TimeControl::ByoYomi { .. } = rules.time_control { Display::Flex } else { Display::None };
+        }
+    }
+    for mut style in settings_query.iter_mut() {
+        style.display = if rules.time_control == TimeControl::Unlimited { Display::None } else { Display::Flex };
+    }
+}
+
+// Functions
+
+fn button_text(button: &MainMenuButton, opponent: &Opponent, rules: &Rules) -> String {
+    match (button, rules.time_control) {
+        (MainMenuButton::Clock, TimeControl::Unlimited) => "No clock".to_string(),
+        (MainMenuButton::Clock, TimeControl::Fischer { base, increment }) => {
+            format!("{} + {}", format_seconds(base), format_seconds(increment))
+        }
+        (MainMenuButton::Clock, TimeControl::ByoYomi { base, period, periods }) => {
+            format!("{}, {} x {} byo-yomi", format_seconds(base), periods, format_seconds(period))
+        }
+        (MainMenuButton::ClockBase, TimeControl::Fischer { base, .. } | TimeControl::ByoYomi { base, .. }) => {
+            format!("Base {}", format_seconds(base))
+        }
+        (MainMenuButton::ClockIncrement, TimeControl::Fischer { increment, .. }) => format!("Inc. {}", format_seconds(increment)),
+        (MainMenuButton::ClockIncrement, TimeControl::ByoYomi { period, .. }) => format!("Period {}", format_seconds(period)),
+        (MainMenuButton::ClockPeriods, TimeControl::ByoYomi { periods, .. }) => format!("{} periods", periods),
+        // Hidden without a clock
+        (MainMenuButton::ClockBase | MainMenuButton::ClockIncrement | MainMenuButton::ClockPeriods, _) => String::new(),
+        (MainMenuButton::Domes, _) => if rules.domes_anywhere {
+            "Domes anywhere".to_string()
+        } else {
+            "Domes on level 3".to_string()
+        },
+        (MainMenuButton::Editor, _) => "Editor".to_string(),
+        (MainMenuButton::FirstPlayer, _) => match rules.first_player {
+            FirstPlayer::Gold => "Gold starts",
+            FirstPlayer::Silver => "Silver starts",
+            FirstPlayer::Random => "Random starter",
+        }.to_string(),
+        (MainMenuButton::Host, _) => "Host game".to_string(),
+        (MainMenuButton::Join, _) => "Join game".to_string(),
+        (MainMenuButton::Opponent, _) => match opponent.controller {
+            Controller::Engine { .. } => "Versus engine",
+            Controller::External => "Versus bot",
+            _ => "Versus human",
+        }.to_string(),
+        (MainMenuButton::Placement, _) => match rules.placement_order {
+            PlacementOrder::Standard => "Standard setup",
+            PlacementOrder::Alternating => "Alternate setup",
+            PlacementOrder::StarterLast => "Starter sets up last",
+        }.to_string(),
+        (MainMenuButton::Play, _) => "Play".to_string(),
+        (MainMenuButton::Puzzles, _) => "Puzzles".to_string(),
+        (MainMenuButton::Quit, _) => "Quit".to_string(),
+        (MainMenuButton::Settings, _) => "Settings".to_string(),
+        (MainMenuButton::Supply, _) => if rules.limited_supply {
+            "Limited supply".to_string()
+        } else {
+            "Unlimited supply".to_string()
+        },
+    }
+}
+
+fn format_seconds(seconds: u64) -> String {
+    if seconds >= 60 && seconds.is_multiple_of(60) {
+        format!("{} min", seconds / 60)
+    } else {
+        format!("{} s", seconds)
+    }
+}
+
+fn next_value<T: Copy + PartialEq>(values: &[T], value: T) -> T {
+    let i = values.iter().position(|x| *x == value).map_or(0, |i| i + 1);
+    values[i % values.len()]
 }
\ No newline at end of file
@@ -1,17 +1,25 @@
+mod game_over_menu;
+mod lobby_menu;
 mod main_menu;
 mod pause_menu;
+mod puzzle_menu;
+mod settings_menu;
 
 use bevy::prelude::*;
 
+use game_over_menu::GameOverMenuPlugin;
+use lobby_menu::LobbyMenuPlugin;
 use main_menu::MainMenuPlugin;
 use pause_menu::PauseMenuPlugin;
+use puzzle_menu::PuzzleMenuPlugin;
+use settings_menu::SettingsMenuPlugin;
 
-pub use pause_menu::Paused;
+pub use pause_menu::{DrawOffer, Paused};
 
 pub struct MenusPlugin;
 impl Plugin for MenusPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_plugins((MainMenuPlugin, PauseMenuPlugin));
+            .add_plugins((GameOverMenuPlugin, LobbyMenuPlugin, MainMenuPlugin, PauseMenuPlugin, PuzzleMenuPlugin, SettingsMenuPlugin));
     }
 }
\ No newline at end of file
@@ -2,7 +2,10 @@ use bevy::prelude::*;
 
 use crate::{
     AppState,
+    board::{Board, Turn, WinReason},
     controller::{Controller, Controllers},
+    network::NetworkSession,
+    puzzle::Puzzle,
 };
 
 pub struct PauseMenuPlugin;
@@ -11,6 +14,8 @@ impl Plugin for PauseMenuPlugin {
         app
             .add_systems(OnEnter(AppState::InGame), setup)
             .add_systems(Update, (
+                draw_prompt,
+                draw_prompt_buttons,
                 pause_button,
                 pause_menu,
             ).run_if(in_state(AppState::InGame)))
@@ -27,6 +32,12 @@ const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
 
 // Resources
 
+#[derive(Default, Resource)]
+pub struct DrawOffer {
+    // Cleared once the opponent answers
+    pub offered_by: Option<Turn>,
+}
+
 #[derive(Resource)]
 pub struct Paused {
     pub value: bool,
@@ -34,12 +45,23 @@ pub struct Paused {
 
 // Components
 
+#[derive(Component)]
+enum DrawPromptButton {
+    Accept,
+    Decline,
+}
+
+#[derive(Component)]
+struct DrawPromptMarker;
+
 #[derive(Component)]
 struct PauseButtonMarker;
 
 #[derive(Component)]
 enum PauseMenuButton {
     Resume,
+    OfferDraw,
+    Resign,
     Reset,
     MainMenu,
 }
@@ -49,23 +71,142 @@ struct PauseMenuMarker;
 
 // Systems
 
+#[allow(clippy::type_complexity)]
 fn cleanup(
     mut commands: Commands,
-    query: Query<Entity, Or<(With<PauseButtonMarker>, With<PauseMenuButton>)>>,
+    query: Query<Entity, Or<(With<DrawPromptMarker>, With<PauseButtonMarker>, With<PauseMenuButton>)>>,
 ) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
+    commands.remove_resource::<DrawOffer>();
     commands.remove_resource::<Paused>();
 }
 
+fn draw_prompt(
+    mut commands: Commands,
+    board: Res<Board>,
+    controllers: Res<Controllers>,
+    draw_offer: Res<DrawOffer>,
+    prompt_query: Query<Entity, With<DrawPromptMarker>>,
+) {
+    const BASE_COLOR: Color = Color::rgba(0.97, 0.97, 1.00, 0.8);
+
+    // Offers are answered by whoever sits on the other side of this screen
+    let playing = matches!(board.get_turn(), Turn::P1 | Turn::P2);
+    let offered_by = draw_offer.offered_by
+        .filter(|x| playing && controller(&controllers, x.opponent()) == Controller::Human);
+
+    match (offered_by, prompt_query.get_single()) {
+        (Some(offered_by), Err(_)) => {
+            let button_style = Style {
+                height: Val::Px(30.0),
+                margin: UiRect::all(Val::Px(5.0)),
+                padding: UiRect::horizontal(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            };
+            let button_text_style = TextStyle {
+                font_size: 20.0,
+                color: Color::rgb(0.95, 0.95, 0.95),
+                ..default()
+            };
+
+            commands
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            width: Val::Percent(100.0),
+                            top: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    DrawPromptMarker,
+                ))
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                padding: UiRect::horizontal(Val::Px(10.0)),
+                                ..default()
+                            },
+                            background_color: BASE_COLOR.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                if offered_by == Turn::P2 { "Silver offers a draw" } else { "Gold offers a draw" },
+                                TextStyle {
+                                    font_size: 20.0,
+                                    color: Color::rgb(0.05, 0.05, 0.25),
+                                    ..default()
+                                },
+                            ));
+
+                            for (button, label) in [(DrawPromptButton::Accept, "Accept"), (DrawPromptButton::Decline, "Decline")] {
+                                parent
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: button_style.clone(),
+                                            background_color: NORMAL_BUTTON_COLOR.into(),
+                                            ..default()
+                                        },
+                                        button,
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn(TextBundle::from_section(label, button_text_style.clone()));
+                                    });
+                            }
+                        });
+                });
+        }
+        (None, Ok(entity)) => commands.entity(entity).despawn_recursive(),
+        _ => {}
+    }
+}
+
+fn draw_prompt_buttons(
+    mut board: ResMut<Board>,
+    mut buttons_query: Query<(&Interaction, &DrawPromptButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut draw_offer: ResMut<DrawOffer>,
+) {
+    for (interaction, button, mut color) in buttons_query.iter_mut() {
+        match (interaction, button) {
+            (Interaction::Pressed, DrawPromptButton::Accept) => {
+                board.draw();
+                draw_offer.offered_by = None;
+            }
+            (Interaction::Pressed, DrawPromptButton::Decline) => draw_offer.offered_by = None,
+            (Interaction::Hovered, _) => *color = HOVERED_BUTTON_COLOR.into(),
+            (Interaction::None, _) => *color = NORMAL_BUTTON_COLOR.into(),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn pause_button(
     mut commands: Commands,
     mut paused: ResMut<Paused>,
+    board: Res<Board>,
     button_query: Query<(Entity, &Interaction), (Changed<Interaction>, With<PauseButtonMarker>)>,
+    controllers: Res<Controllers>,
+    draw_offer: Res<DrawOffer>,
+    network_session: Option<Res<NetworkSession>>,
+    puzzle: Option<Res<Puzzle>>,
 ) {
     const BASE_COLOR: Color = Color::rgba(0.97, 0.97, 1.00, 0.8);
 
+    // Draws are only offered to human opponents, and never in puzzles
+    let player = local_player(&board, &controllers, network_session.as_deref());
+    let can_offer_draw = puzzle.is_none()
+        && draw_offer.offered_by.is_none()
+        && player.is_some_and(|x| matches!(controller(&controllers, x.opponent()), Controller::Human | Controller::Network));
+
     for (entity, interaction) in button_query.iter() {
             if *interaction == Interaction::Pressed {
                 paused.value = true;
@@ -121,19 +262,54 @@ fn pause_button(
                             ));
                         });
 
-                        parent.spawn((
-                            ButtonBundle {
-                                style: button_style.clone(),
-                                background_color: NORMAL_BUTTON_COLOR.into(),
-                                ..default()
-                            },
-                            PauseMenuButton::Reset,
-                        )).with_children(|parent| {
-                            parent.spawn(TextBundle::from_section(
-                                "Reset",
-                                button_text_style.clone(),
-                            ));
-                        });
+                        if can_offer_draw {
+                            parent.spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                PauseMenuButton::OfferDraw,
+                            )).with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    "Offer Draw",
+                                    button_text_style.clone(),
+                                ));
+                            });
+                        }
+
+                        if player.is_some() {
+                            parent.spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                PauseMenuButton::Resign,
+                            )).with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    "Resign",
+                                    button_text_style.clone(),
+                                ));
+                            });
+                        }
+
+                        // Network games can't be restarted by one side alone
+                        if network_session.is_none() {
+                            parent.spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                PauseMenuButton::Reset,
+                            )).with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    "Reset",
+                                    button_text_style.clone(),
+                                ));
+                            });
+                        }
 
                         parent.spawn((
                             ButtonBundle {
@@ -154,17 +330,27 @@ fn pause_button(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn pause_menu(
+    mut board: ResMut<Board>,
     mut buttons_query: Query<(&Interaction, &PauseMenuButton, &mut BackgroundColor), Changed<Interaction>>,
     mut commands: Commands,
+    mut draw_offer: ResMut<DrawOffer>,
     mut next_state: ResMut<NextState<AppState>>,
     mut paused: ResMut<Paused>,
     asset_server: Res<AssetServer>,
+    controllers: Res<Controllers>,
+    network_session: Option<Res<NetworkSession>>,
     pause_menu_query: Query<Entity, With<PauseMenuMarker>>,
 ) {
     for (interaction, button, mut color) in buttons_query.iter_mut() {
         match (interaction, button) {
-            (Interaction::Pressed, PauseMenuButton::Resume) => {
+            (Interaction::Pressed, PauseMenuButton::Resume | PauseMenuButton::OfferDraw | PauseMenuButton::Resign) => {
+                match (button, local_player(&board, &controllers, network_session.as_deref())) {
+                    (PauseMenuButton::OfferDraw, Some(player)) => draw_offer.offered_by = Some(player),
+                    (PauseMenuButton::Resign, Some(player)) => board.concede(player, WinReason::Resignation),
+                    _ => {}
+                }
                 paused.value = false;
                 commands.entity(pause_menu_query.single()).despawn_recursive();
                 spawn_pause_button(&mut commands, Res::clone(&asset_server));
@@ -178,13 +364,8 @@ fn pause_menu(
 }
 
 fn reset(
-    mut commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    commands.insert_resource(Controllers {
-        p1: Controller::Human,
-        p2: Controller::Human,
-    });
     next_state.set(AppState::InGame);
 }
 
@@ -192,12 +373,38 @@ fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
 ) {
+    commands.init_resource::<DrawOffer>();
     commands.insert_resource(Paused { value: false });
     spawn_pause_button(&mut commands, asset_server);
 }
 
 // Functions
 
+fn controller(controllers: &Controllers, turn: Turn) -> Controller {
+    if turn == Turn::P2 { controllers.p2 } else { controllers.p1 }
+}
+
+// The player resigning or offering a draw: the local player of a network game, the only human
+// against a bot, or the player to move when two humans share the screen
+fn local_player(
+    board: &Board,
+    controllers: &Controllers,
+    network_session: Option<&NetworkSession>,
+) -> Option<Turn> {
+    if !matches!(board.get_turn(), Turn::P1 | Turn::P2) {
+        return None;
+    }
+    if let Some(session) = network_session {
+        return session.get_local();
+    }
+    match (controllers.p1, controllers.p2) {
+        (Controller::Human, Controller::Human) => Some(*board.get_turn()),
+        (Controller::Human, _) => Some(Turn::P1),
+        (_, Controller::Human) => Some(Turn::P2),
+        _ => None,
+    }
+}
+
 fn spawn_pause_button(
     commands: &mut Commands,
     asset_server: Res<AssetServer>,
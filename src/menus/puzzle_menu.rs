@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppState,
+    board::{StartingPosition, Turn},
+    controller::{Controller, Controllers},
+    puzzle::{PuzzlePack, load_puzzle_packs},
+};
+
+pub struct PuzzleMenuPlugin;
+impl Plugin for PuzzleMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::PuzzleMenu), setup)
+            .add_systems(Update, buttons_system.run_if(in_state(AppState::PuzzleMenu)))
+            .add_systems(OnExit(AppState::PuzzleMenu), cleanup);
+    }
+}
+
+// Constants
+
+const DEFENDER_DEPTH: usize = 3;
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.65);
+const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
+
+// Resources
+
+#[derive(Resource)]
+struct PuzzlePacks {
+    packs: Vec<PuzzlePack>,
+}
+
+// Components
+
+#[derive(Component)]
+enum PuzzleMenuButton {
+    Back,
+    Puzzle {
+        pack: usize,
+        puzzle: usize,
+    },
+}
+
+#[derive(Component)]
+struct PuzzleMenuMarker;
+
+// Systems
+
+#[allow(clippy::type_complexity)]
+fn buttons_system(
+    mut commands: Commands,
+    mut interaction_query: Query<
+        (&Interaction, &PuzzleMenuButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut next_state: ResMut<NextState<AppState>>,
+    puzzle_packs: Res<PuzzlePacks>,
+) {
+    for (interaction, button, mut color) in &mut interaction_query {
+        *color = match *interaction {
+            Interaction::Pressed => {
+                match *button {
+                    PuzzleMenuButton::Back => next_state.set(AppState::Menu),
+                    PuzzleMenuButton::Puzzle { pack, puzzle } => {
+                        let puzzle = puzzle_packs.packs[pack].puzzles[puzzle].clone();
+                        let (p1, p2) = match puzzle.board.get_turn() {
+                            Turn::P1 => (Controller::Human, Controller::Engine { depth: DEFENDER_DEPTH }),
+                            _ => (Controller::Engine { depth: DEFENDER_DEPTH }, Controller::Human),
+                        };
+
+                        commands.insert_resource(Controllers { p1, p2 });
+                        commands.insert_resource(StartingPosition { board: puzzle.board.clone() });
+                        commands.insert_resource(puzzle);
+                        next_state.set(AppState::InGame);
+                    }
+                }
+                continue;
+            }
+            Interaction::Hovered => HOVERED_BUTTON_COLOR.into(),
+            Interaction::None => NORMAL_BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<PuzzleMenuMarker>>,
+) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<PuzzlePacks>();
+}
+
+fn setup(
+    mut commands: Commands,
+) {
+    commands.spawn((PuzzleMenuMarker, Camera2dBundle::default()));
+
+    const BASE_COLOR: Color = Color::rgb(0.97, 0.97, 1.00);
+
+    let button_style = Style {
+        width: Val::Px(400.0),
+        height: Val::Px(40.0),
+        margin: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 24.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+    let pack_style = TextStyle {
+        font_size: 32.0,
+        color: Color::rgb(0.05, 0.05, 0.65),
+        ..default()
+    };
+    let title_style = TextStyle {
+        font_size: 60.0,
+        color: Color::rgb(0.05, 0.05, 0.65),
+        ..default()
+    };
+
+    let packs = load_puzzle_packs();
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            PuzzleMenuMarker,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: BASE_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section("Puzzles", title_style)
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(15.0)),
+                                ..default()
+                            }),
+                    );
+
+                    for (i, pack) in packs.iter().enumerate() {
+                        parent.spawn(TextBundle::from_section(pack.name.clone(), pack_style.clone()));
+
+                        for (j, puzzle) in pack.puzzles.iter().enumerate() {
+                            parent
+                                .spawn((
+                                    ButtonBundle {
+                                        style: button_style.clone(),
+                                        background_color: NORMAL_BUTTON_COLOR.into(),
+                                        ..default()
+                                    },
+                                    PuzzleMenuButton::Puzzle { pack: i, puzzle: j },
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(TextBundle::from_section(
+                                        format!("{} (win in {})", puzzle.name, puzzle.turns),
+                                        button_text_style.clone(),
+                                    ));
+                                });
+                        }
+                    }
+
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: NORMAL_BUTTON_COLOR.into(),
+                                ..default()
+                            },
+                            PuzzleMenuButton::Back,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "Back",
+                                button_text_style.clone(),
+                            ));
+                        });
+                });
+        });
+
+    commands.insert_resource(PuzzlePacks { packs });
+}
\ No newline at end of file
@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppState,
+    animation::AnimationSpeed,
+    camera::CameraSettings,
+    environment::GraphicsQuality,
+    flat_view::ViewMode,
+    labels::LabelSettings,
+    theme::Theme,
+};
+
+pub struct SettingsMenuPlugin;
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::Settings), setup)
+            .add_systems(Update, buttons_system.run_if(in_state(AppState::Settings)))
+            .add_systems(OnExit(AppState::Settings), cleanup);
+    }
+}
+
+// Constants
+
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.65);
+const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
+
+// Components
+
+#[derive(Clone, Copy, Component)]
+enum SettingsMenuButton {
+    Animations,
+    Back,
+    Camera,
+    Graphics,
+    Labels,
+    Theme,
+    View,
+}
+
+#[derive(Component)]
+struct SettingsMenuMarker;
+
+// Systems
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn buttons_system(
+    mut animation_speed: ResMut<AnimationSpeed>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut graphics_quality: ResMut<GraphicsQuality>,
+    mut interaction_query: Query<
+        (&Interaction, &SettingsMenuButton, &mut BackgroundColor, &Children),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut label_settings: ResMut<LabelSettings>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut text_query: Query<&mut Text>,
+    mut theme: ResMut<Theme>,
+    mut view_mode: ResMut<ViewMode>,
+) {
+    for (interaction, button, mut color, children) in &mut interaction_query {
+        *color = match *interaction {
+            Interaction::Pressed => {
+                match *button {
+                    SettingsMenuButton::Animations => {
+                        *animation_speed = match *animation_speed {
+                            AnimationSpeed::Normal => AnimationSpeed::Fast,
+                            AnimationSpeed::Fast => AnimationSpeed::Off,
+                            AnimationSpeed::Off => AnimationSpeed::Normal,
+                        };
+                    }
+                    SettingsMenuButton::Back => next_state.set(AppState::Menu),
+                    SettingsMenuButton::Camera => camera_settings.follow_turn = !camera_settings.follow_turn,
+                    SettingsMenuButton::Graphics => {
+                        *graphics_quality = match *graphics_quality {
+                            GraphicsQuality::Low => GraphicsQuality::Medium,
+                            GraphicsQuality::Medium => GraphicsQuality::High,
+                            GraphicsQuality::High => GraphicsQuality::Low,
+                        };
+                    }
+                    SettingsMenuButton::Labels => label_settings.visible = !label_settings.visible,
+                    SettingsMenuButton::Theme => {
+                        let i = Theme::ALL.iter().position(|x| *x == *theme).unwrap_or(0);
+                        *theme = Theme::ALL[(i + 1) % Theme::ALL.len()];
+                    }
+                    SettingsMenuButton::View => {
+                        *view_mode = match *view_mode {
+                            ViewMode::Flat => ViewMode::Scene,
+                            ViewMode::Scene => ViewMode::Flat,
+                        };
+                    }
+                }
+                if let Ok(mut text) = text_query.get_mut(children[0]) {
+                    text.sections[0].value = button_text(button, &animation_speed, &camera_settings, &graphics_quality, &label_settings, &theme, &view_mode).to_string();
+                }
+                continue;
+            }
+            Interaction::Hovered => HOVERED_BUTTON_COLOR.into(),
+            Interaction::None => NORMAL_BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<SettingsMenuMarker>>,
+) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    animation_speed: Res<AnimationSpeed>,
+    camera_settings: Res<CameraSettings>,
+    graphics_quality: Res<GraphicsQuality>,
+    label_settings: Res<LabelSettings>,
+    theme: Res<Theme>,
+    view_mode: Res<ViewMode>,
+) {
+    commands.spawn((SettingsMenuMarker, Camera2dBundle::default()));
+
+    const BASE_COLOR: Color = Color::rgb(0.97, 0.97, 1.00);
+
+    let button_style = Style {
+        width: Val::Px(320.0),
+        height: Val::Px(36.0),
+        margin: UiRect::all(Val::Px(3.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 30.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+    let hint_style = TextStyle {
+        font_size: 20.0,
+        color: Color::rgb(0.05, 0.05, 0.25),
+        ..default()
+    };
+    let title_style = TextStyle {
+        font_size: 60.0,
+        color: Color::rgb(0.05, 0.05, 0.65),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            SettingsMenuMarker,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::bottom(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: BASE_COLOR.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section("Settings", title_style)
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(15.0)),
+                                ..default()
+                            }),
+                    );
+
+                    for button in [
+                        SettingsMenuButton::Theme,
+                        SettingsMenuButton::Graphics,
+                        SettingsMenuButton::Animations,
+                        SettingsMenuButton::View,
+                        SettingsMenuButton::Labels,
+                        SettingsMenuButton::Camera,
+                        SettingsMenuButton::Back,
+                    ] {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                button,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    button_text(&button, &animation_speed, &camera_settings, &graphics_quality, &label_settings, &theme, &view_mode),
+                                    button_text_style.clone(),
+                                ));
+                            });
+                    }
+
+                    parent.spawn(
+                        TextBundle::from_section(
+                            "In game: V switches the view, L the labels, F the camera and H the moves\n\
+                            1-4 pick a camera preset, the arrows and mouse move it",
+                            hint_style,
+                        )
+                            .with_text_alignment(TextAlignment::Center)
+                            .with_style(Style {
+                                margin: UiRect::all(Val::Px(10.0)),
+                                ..default()
+                            }),
+                    );
+                });
+        });
+}
+
+// Functions
+
+fn button_text(
+    button: &SettingsMenuButton,
+    animation_speed: &AnimationSpeed,
+    camera_settings: &CameraSettings,
+    graphics_quality: &GraphicsQuality,
+    label_settings: &LabelSettings,
+    theme: &Theme,
+    view_mode: &ViewMode,
+) -> &'static str {
+    match button {
+        SettingsMenuButton::Animations => match animation_speed {
+            AnimationSpeed::Normal => "Normal animations",
+            AnimationSpeed::Fast => "Fast animations",
+            AnimationSpeed::Off => "No animations",
+        },
+        SettingsMenuButton::Back => "Back",
+        SettingsMenuButton::Camera => if camera_settings.follow_turn {
+            "Camera follows the turn"
+        } else {
+            "Camera stays put"
+        },
+        SettingsMenuButton::Graphics => match graphics_quality {
+            GraphicsQuality::Low => "Low graphics",
+            GraphicsQuality::Medium => "Medium graphics",
+            GraphicsQuality::High => "High graphics, with scenery",
+        },
+        SettingsMenuButton::Labels => if label_settings.visible {
+            "Heights and coordinates"
+        } else {
+            "No labels"
+        },
+        SettingsMenuButton::Theme => match theme {
+            Theme::Classic => "Classic colours",
+            Theme::BlueOrange => "Blue and orange",
+            Theme::RedTeal => "Red and teal",
+            Theme::Contrast => "High contrast",
+        },
+        SettingsMenuButton::View => match view_mode {
+            ViewMode::Flat => "Flat board",
+            ViewMode::Scene => "3D board",
+        },
+    }
+}
\ No newline at end of file
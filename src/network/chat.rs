@@ -0,0 +1,304 @@
+use bevy::prelude::*;
+
+use super::{NetworkSession, protocol::Message};
+use crate::{
+    AppState,
+    board::Turn,
+    theme::Theme,
+};
+
+pub struct ChatPlugin;
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame),
+                setup.run_if(resource_exists::<NetworkSession>())
+            )
+            .add_systems(Update, (
+                buttons_system,
+                type_message,
+                update_chat,
+            ).run_if(in_state(AppState::InGame).and_then(resource_exists::<NetworkSession>())))
+            .add_systems(OnExit(AppState::InGame), cleanup);
+    }
+}
+
+// Constants
+
+const EMOTES: [(&str, &str); 5] = [
+    ("hello", "Hello!"),
+    ("well-played", "Well played!"),
+    ("oops", "Oops!"),
+    ("thinking", "Hmm..."),
+    ("good-game", "Good game!"),
+];
+const HOVERED_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.65);
+const MAX_LENGTH: usize = 120;
+const NORMAL_BUTTON_COLOR: Color = Color::rgb(0.05, 0.05, 0.25);
+const VISIBLE_LINES: usize = 8;
+
+// Structs
+
+#[derive(Clone)]
+pub struct ChatLine {
+    // Emotes keep their name as text, unknown ones are shown as is
+    pub emote: bool,
+    pub text: String,
+    pub turn: Turn,
+}
+
+impl ChatLine {
+    pub fn message(&self) -> Message {
+        if self.emote {
+            Message::Emote { name: self.text.clone(), turn: self.turn }
+        } else {
+            Message::Chat { text: self.text.clone(), turn: self.turn }
+        }
+    }
+    fn display(&self) -> String {
+        let player = if self.turn == Turn::P2 { "Silver" } else { "Gold" };
+        if self.emote {
+            let label = EMOTES.iter().find(|(name, _)| *name == self.text).map_or(self.text.as_str(), |(_, label)| label);
+            format!("{}: [{}]\n", player, label)
+        } else {
+            format!("{}: {}\n", player, self.text)
+        }
+    }
+}
+
+// Resources
+
+#[derive(Default, Resource)]
+pub struct ChatInput {
+    muted: bool,
+    typing: Option<String>,
+}
+
+impl ChatInput {
+    pub fn is_typing(&self) -> bool {
+        self.typing.is_some()
+    }
+}
+
+// Components
+
+#[derive(Component)]
+enum ChatButton {
+    Emote(usize),
+    Mute,
+}
+
+#[derive(Component)]
+struct ChatMarker;
+
+#[derive(Component)]
+struct ChatText;
+
+// Systems
+
+#[allow(clippy::type_complexity)]
+fn buttons_system(
+    mut input: ResMut<ChatInput>,
+    mut interaction_query: Query<
+        (&Interaction, &ChatButton, &Children, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut session: ResMut<NetworkSession>,
+    mut text_query: Query<&mut Text, Without<ChatText>>,
+) {
+    let Some(local) = session.local else {
+        return;
+    };
+
+    for (interaction, button, children, mut color) in &mut interaction_query {
+        *color = match *interaction {
+            Interaction::Pressed => {
+                match *button {
+                    ChatButton::Emote(index) => session.say(ChatLine {
+                        emote: true,
+                        text: EMOTES[index].0.to_string(),
+                        turn: local,
+                    }),
+                    ChatButton::Mute => {
+                        input.muted = !input.muted;
+                        if let Ok(mut text) = text_query.get_mut(children[0]) {
+                            text.sections[0].value = if input.muted { "Unmute" } else { "Mute" }.to_string();
+                        }
+                    }
+                }
+                continue;
+            }
+            Interaction::Hovered => HOVERED_BUTTON_COLOR.into(),
+            Interaction::None => NORMAL_BUTTON_COLOR.into(),
+        };
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<ChatMarker>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<ChatInput>();
+}
+
+fn setup(
+    mut commands: Commands,
+    session: Res<NetworkSession>,
+) {
+    commands.init_resource::<ChatInput>();
+
+    let button_style = Style {
+        height: Val::Px(24.0),
+        margin: UiRect::all(Val::Px(2.0)),
+        padding: UiRect::horizontal(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 14.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(5.0),
+                    bottom: Val::Px(30.0),
+                    width: Val::Px(360.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            ChatMarker,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section("", TextStyle {
+                    color: Color::WHITE,
+                    font_size: 16.0,
+                    ..default()
+                }),
+                ChatText,
+            ));
+
+            // Spectators only read along
+            if session.local.is_none() {
+                return;
+            }
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_wrap: FlexWrap::Wrap,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let buttons = EMOTES
+                        .iter()
+                        .enumerate()
+                        .map(|(index, (_, label))| (ChatButton::Emote(index), *label))
+                        .chain([(ChatButton::Mute, "Mute")]);
+                    for (button, label) in buttons {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: button_style.clone(),
+                                    background_color: NORMAL_BUTTON_COLOR.into(),
+                                    ..default()
+                                },
+                                button,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(label, button_text_style.clone()));
+                            });
+                    }
+                });
+        });
+}
+
+fn type_message(
+    mut ev_character: EventReader<ReceivedCharacter>,
+    mut input: ResMut<ChatInput>,
+    mut session: ResMut<NetworkSession>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let Some(local) = session.local else {
+        ev_character.clear();
+        return;
+    };
+
+    // Return opens the chat line and sends it, escape drops it
+    let Some(typing) = input.typing.as_mut() else {
+        ev_character.clear();
+        if keys.just_pressed(KeyCode::Return) {
+            input.typing = Some(String::new());
+        }
+        return;
+    };
+
+    for ReceivedCharacter { window: _, char } in ev_character.read() {
+        if !char.is_control() && typing.chars().count() < MAX_LENGTH {
+            typing.push(*char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        typing.pop();
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        input.typing = None;
+    } else if keys.just_pressed(KeyCode::Return) {
+        let text = typing.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !text.is_empty() {
+            session.say(ChatLine { emote: false, text, turn: local });
+        }
+        input.typing = None;
+    }
+}
+
+fn update_chat(
+    mut text_query: Query<&mut Text, With<ChatText>>,
+    input: Res<ChatInput>,
+    session: Res<NetworkSession>,
+    theme: Res<Theme>,
+) {
+    // Muting hides the opponent's lines, they're still kept in the history
+    let mut lines: Vec<&ChatLine> = session.chat
+        .iter()
+        .filter(|x| !input.muted || Some(x.turn) == session.local)
+        .collect();
+    lines.drain(..lines.len().saturating_sub(VISIBLE_LINES));
+
+    let mut sections: Vec<TextSection> = lines
+        .into_iter()
+        .map(|x| TextSection::new(x.display(), TextStyle {
+            color: theme.palette().player(x.turn),
+            font_size: 16.0,
+            ..default()
+        }))
+        .collect();
+    if let Some(typing) = &input.typing {
+        sections.push(TextSection::new(format!("> {}_", typing), TextStyle {
+            color: Color::WHITE,
+            font_size: 16.0,
+            ..default()
+        }));
+    }
+
+    for mut text in text_query.iter_mut() {
+        let unchanged = text.sections.len() == sections.len()
+            && text.sections.iter().zip(&sections).all(|(x, y)| x.value == y.value);
+        if !unchanged {
+            text.sections = sections.clone();
+        }
+    }
+}
\ No newline at end of file
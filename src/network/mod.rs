@@ -0,0 +1,841 @@
+mod chat;
+mod protocol;
+pub mod server;
+
+use bevy::prelude::*;
+
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on};
+use bevy_mod_picking::backends::raycast::bevy_mod_raycast::prelude::NoBackfaceCulling;
+use std::{
+    io,
+    mem,
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{
+    AppState,
+    board::{Board, FirstPlayer, PickBlocker, Rules, StartingPosition, Turn, WinReason},
+    controller::{Controller, Controllers},
+    menus::DrawOffer,
+    notation::{format_ply, parse_ply},
+    puzzle::Puzzle,
+};
+
+pub use chat::{ChatInput, ChatLine};
+use chat::ChatPlugin;
+pub use protocol::DEFAULT_PORT;
+use protocol::{Connection, DrawAction, Message, PROTOCOL_VERSION, session_token};
+
+pub struct NetworkPlugin;
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(ChatPlugin)
+            .add_systems(Update,
+                poll_lobby.run_if(in_state(AppState::Lobby).and_then(resource_exists::<NetworkLobby>()))
+            )
+            .add_systems(OnExit(AppState::Lobby), cleanup_lobby)
+            .add_systems(OnEnter(AppState::InGame),
+                setup.run_if(resource_exists::<NetworkSession>())
+            )
+            .add_systems(Update, (
+                sync_network,
+                update_overlay,
+            ).chain().run_if(in_state(AppState::InGame).and_then(resource_exists::<NetworkSession>())))
+            .add_systems(OnExit(AppState::InGame), cleanup);
+    }
+}
+
+// Constants
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// A hosted game is the only one its host lists
+const HOSTED_GAME: usize = 1;
+const RECONNECT_INTERVAL: f32 = 2.0;
+
+// Structs
+
+struct Guests {
+    broadcast: usize,
+    chat_broadcast: usize,
+    draw_broadcast: bool,
+    listener: TcpListener,
+    pending: Vec<Connection>,
+    watching: Vec<Connection>,
+}
+
+#[derive(Clone)]
+enum JoinKind {
+    List,
+    Play,
+    Resume {
+        token: String,
+    },
+    Watch,
+}
+
+enum LobbyStage {
+    Connecting {
+        kind: JoinKind,
+        task: Task<io::Result<TcpStream>>,
+    },
+    Failed,
+    Handshake {
+        connection: Connection,
+        kind: JoinKind,
+        listener: Option<TcpListener>,
+        seat: Option<Turn>,
+        token: Option<String>,
+    },
+    Listening(TcpListener),
+}
+
+// Resources
+
+#[derive(Resource)]
+pub struct LastSession {
+    address: String,
+    token: String,
+}
+
+#[derive(Resource)]
+pub struct NetworkLobby {
+    // The host's address, when joining
+    address: Option<String>,
+    // Open games, once the host listed them
+    pub games: Option<Vec<usize>>,
+    pub status: String,
+    stage: LobbyStage,
+}
+
+impl NetworkLobby {
+    pub fn host(port: u16) -> Self {
+        match listen(port) {
+            Ok(listener) => Self {
+                address: None,
+                games: None,
+                status: format!("Waiting for an opponent on port {}", port),
+                stage: LobbyStage::Listening(listener),
+            },
+            Err(error) => Self {
+                address: None,
+                games: None,
+                status: format!("Couldn't listen on port {}: {}", port, error),
+                stage: LobbyStage::Failed,
+            },
+        }
+    }
+    pub fn join(address: &str) -> Self {
+        Self::connect(address, JoinKind::Play)
+    }
+    // Joins one of the listed games over the connection that listed them
+    pub fn join_game(&mut self, id: usize) {
+        if let LobbyStage::Handshake { connection, kind: kind @ JoinKind::List, .. } = &mut self.stage {
+            connection.send(&Message::Join { id, version: PROTOCOL_VERSION });
+            *kind = JoinKind::Play;
+            self.games = None;
+            self.status = format!("Joining game {}", id);
+        }
+    }
+    pub fn list(address: &str) -> Self {
+        Self::connect(address, JoinKind::List)
+    }
+    pub fn resume(last_session: &LastSession) -> Self {
+        Self::connect(&last_session.address, JoinKind::Resume { token: last_session.token.clone() })
+    }
+    pub fn watch(address: &str) -> Self {
+        Self::connect(address, JoinKind::Watch)
+    }
+    fn connect(address: &str, kind: JoinKind) -> Self {
+        let address = if address.contains(':') { address.to_string() } else { format!("{}:{}", address, DEFAULT_PORT) };
+
+        Self {
+            games: None,
+            status: format!("Connecting to {}", address),
+            stage: LobbyStage::Connecting { kind, task: connect(address.clone()) },
+            address: Some(address),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct NetworkSession {
+    // Clients reconnect to the host at this address
+    address: Option<String>,
+    chat: Vec<ChatLine>,
+    // The opponent, or the host when spectating
+    connection: Option<Connection>,
+    // The draw offer as the opponent last heard of it
+    draw_sent: Option<Turn>,
+    // Only the host accepts guests, which are spectators or a returning opponent
+    guests: Option<Guests>,
+    last_turn: Turn,
+    // None when spectating
+    local: Option<Turn>,
+    // Set while the server reports the opponent's connection dropped
+    opponent_away: bool,
+    reconnecting: Option<Task<io::Result<TcpStream>>>,
+    resign_sent: bool,
+    resigned: Option<Turn>,
+    // Number of plies the host is replaying after a reconnection
+    resuming: Option<usize>,
+    retry: Timer,
+    rules: Rules,
+    status: String,
+    synced: usize,
+    token: Option<String>,
+}
+
+impl NetworkSession {
+    pub fn get_chat(&self) -> &[ChatLine] {
+        &self.chat
+    }
+    pub fn get_local(&self) -> Option<Turn> {
+        self.local
+    }
+    pub fn get_rules(&self) -> &Rules {
+        &self.rules
+    }
+    fn say(&mut self, line: ChatLine) {
+        if let Some(connection) = self.connection.as_mut() {
+            connection.send(&line.message());
+        }
+        self.chat.push(line);
+    }
+}
+
+// Components
+
+#[derive(Component)]
+struct BlockerMarker;
+
+#[derive(Component)]
+struct NetworkText;
+
+#[derive(Component)]
+struct WaitingOverlay;
+
+// Systems
+
+#[allow(clippy::type_complexity)]
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, Or<(With<BlockerMarker>, With<NetworkText>, With<WaitingOverlay>)>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.remove_resource::<NetworkSession>();
+}
+
+fn cleanup_lobby(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<NetworkLobby>();
+}
+
+fn poll_lobby(
+    mut commands: Commands,
+    mut lobby: ResMut<NetworkLobby>,
+    mut next_state: ResMut<NextState<AppState>>,
+    rules: Res<Rules>,
+) {
+    let NetworkLobby { address, games, status, stage } = &mut *lobby;
+
+    match stage {
+        LobbyStage::Connecting { kind, task } if task.is_finished() => {
+            match block_on(task).and_then(Connection::new) {
+                Ok(mut connection) => {
+                    connection.send(&match kind {
+                        JoinKind::List => Message::List,
+                        JoinKind::Play => Message::Hello { version: PROTOCOL_VERSION },
+                        JoinKind::Resume { token } => Message::Resume { token: token.clone(), version: PROTOCOL_VERSION },
+                        JoinKind::Watch => Message::Watch { version: PROTOCOL_VERSION },
+                    });
+                    *status = format!("Connected to {}, waiting for the host", connection.peer());
+                    *stage = LobbyStage::Handshake { connection, kind: kind.clone(), listener: None, seat: None, token: None };
+                }
+                Err(error) => {
+                    *status = format!("Couldn't connect: {}", error);
+                    *stage = LobbyStage::Failed;
+                }
+            }
+        }
+        LobbyStage::Listening(listener) => match listener.accept().and_then(|(x, _)| Connection::new(x)) {
+            Ok(connection) => {
+                let LobbyStage::Listening(listener) = mem::replace(stage, LobbyStage::Failed) else {
+                    unreachable!();
+                };
+                *stage = LobbyStage::Handshake { connection, kind: JoinKind::Play, listener: Some(listener), seat: None, token: None };
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => {}
+            Err(error) => warn!("Couldn't accept a connection: {}", error),
+        },
+        LobbyStage::Handshake { connection, kind, listener, seat, token } => {
+            match handshake(connection, listener.is_some(), kind, games, seat, token, &rules) {
+                Ok(None) => if let (JoinKind::List, Some(games)) = (kind, games) {
+                    *status = match games.len() {
+                        0 => "There are no open games".to_string(),
+                        1 => "There is 1 open game".to_string(),
+                        count => format!("There are {} open games", count),
+                    };
+                },
+                Ok(Some((board, rules, local))) => {
+                    let LobbyStage::Handshake { connection, listener, token, .. } = mem::replace(stage, LobbyStage::Failed) else {
+                        unreachable!();
+                    };
+                    let (p1, p2) = match local {
+                        Some(Turn::P1) => (Controller::Human, Controller::Network),
+                        Some(_) => (Controller::Network, Controller::Human),
+                        None => (Controller::Network, Controller::Network),
+                    };
+                    if let (Some(address), Some(token), Some(_)) = (address.clone(), token.clone(), local) {
+                        commands.insert_resource(LastSession { address, token });
+                    }
+
+                    commands.insert_resource(Controllers { p1, p2 });
+                    commands.insert_resource(NetworkSession {
+                        address: address.clone(),
+                        chat: Vec::new(),
+                        status: if local.is_some() {
+                            format!("Playing against {}", connection.peer())
+                        } else {
+                            format!("Watching the game hosted by {}", connection.peer())
+                        },
+                        connection: Some(connection),
+                        draw_sent: None,
+                        guests: listener.map(|listener| Guests {
+                            broadcast: 0,
+                            chat_broadcast: 0,
+                            draw_broadcast: false,
+                            listener,
+                            pending: Vec::new(),
+                            watching: Vec::new(),
+                        }),
+                        last_turn: *board.get_turn(),
+                        local,
+                        opponent_away: false,
+                        reconnecting: None,
+                        resign_sent: false,
+                        resigned: None,
+                        resuming: None,
+                        retry: Timer::from_seconds(RECONNECT_INTERVAL, TimerMode::Repeating),
+                        rules,
+                        synced: 0,
+                        token,
+                    });
+                    commands.insert_resource(StartingPosition { board });
+                    commands.remove_resource::<Puzzle>();
+                    next_state.set(AppState::InGame);
+                }
+                Err(reason) => {
+                    let LobbyStage::Handshake { listener, .. } = mem::replace(stage, LobbyStage::Failed) else {
+                        unreachable!();
+                    };
+                    match listener {
+                        Some(listener) => {
+                            *status = format!("{}, still waiting for an opponent", reason);
+                            *stage = LobbyStage::Listening(listener);
+                        }
+                        None => *status = reason,
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section("", TextStyle {
+                color: Color::WHITE,
+                font_size: 20.0,
+                ..default()
+            }),
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(5.0),
+                bottom: Val::Px(5.0),
+                ..default()
+            },
+            ..default()
+        },
+        NetworkText,
+    ));
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    top: Val::Percent(40.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            WaitingOverlay,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section("", TextStyle {
+                    color: Color::WHITE,
+                    font_size: 40.0,
+                    ..default()
+                })
+                    .with_style(Style {
+                        padding: UiRect::all(Val::Px(15.0)),
+                        ..default()
+                    })
+                    .with_background_color(Color::rgba(0.05, 0.05, 0.25, 0.8)),
+            );
+        });
+}
+
+fn sync_network(
+    mut board: ResMut<Board>,
+    mut draw_offer: ResMut<DrawOffer>,
+    mut session: ResMut<NetworkSession>,
+    time: Res<Time>,
+) {
+    let NetworkSession {
+        address,
+        chat,
+        connection,
+        draw_sent,
+        guests,
+        last_turn,
+        local,
+        opponent_away,
+        reconnecting,
+        resign_sent,
+        resigned,
+        resuming,
+        retry,
+        rules,
+        status,
+        synced,
+        token,
+    } = &mut *session;
+    let was_resigned = resigned.is_some();
+    let playing = matches!(board.get_turn(), Turn::P1 | Turn::P2);
+
+    if let Some(current) = connection.as_mut() {
+        if let Err(error) = receive(current, &mut board, chat, &mut draw_offer, *local, opponent_away, resigned, resuming, synced) {
+            *status = match (error.kind(), *local, &address) {
+                (io::ErrorKind::ConnectionAborted, Some(_), Some(_)) => "Lost the connection to the host".to_string(),
+                (io::ErrorKind::ConnectionAborted, Some(_), None) => "Opponent disconnected".to_string(),
+                (io::ErrorKind::ConnectionAborted, None, _) => "Host disconnected".to_string(),
+                _ => format!("Disconnected: {}", error),
+            };
+            *connection = None;
+            *resign_sent = false;
+        }
+    }
+
+    // Clients keep trying to get back to the host, which keeps the authoritative game
+    if let (None, Some(_), Some(address), Some(token), true) = (connection.as_ref(), *local, address.as_ref(), token.as_ref(), playing) {
+        match reconnecting.take() {
+            Some(task) if task.is_finished() => match block_on(task).and_then(Connection::new) {
+                Ok(mut current) => {
+                    current.send(&Message::Resume { token: token.clone(), version: PROTOCOL_VERSION });
+                    *status = format!("Playing against {}", current.peer());
+                    *connection = Some(current);
+                    *opponent_away = false;
+                    *resuming = Some(usize::MAX);
+                }
+                Err(error) => *status = format!("Couldn't reconnect: {}", error),
+            },
+            Some(task) => *reconnecting = Some(task),
+            None => if retry.tick(time.delta()).just_finished() {
+                *reconnecting = Some(connect(address.clone()));
+            },
+        }
+    }
+
+    // Forfeits don't leave a ply behind, so they're announced explicitly, as are resignations which
+    // can come at any time
+    if let Some(local) = *local {
+        let remote_win = if local == Turn::P1 { Turn::WinP2 } else { Turn::WinP1 };
+        let resigning = board.get_result().is_some_and(|x| x.reason == WinReason::Resignation);
+        if (*last_turn == local || resigning) && *board.get_turn() == remote_win {
+            *resigned = Some(local);
+        }
+
+        if let (Some(current), None) = (connection.as_mut(), *resuming) {
+            for (index, ply) in board.get_history().iter().enumerate().skip(*synced) {
+                current.send(&Message::Ply { index, ply: format_ply(ply) });
+            }
+            *synced = board.get_history().len();
+
+            if *resigned == Some(local) && !*resign_sent {
+                current.send(&resignation(&board, local));
+                *resign_sent = true;
+            }
+
+            // Offers the opponent made are answered once the offer is gone from here
+            match (*draw_sent, draw_offer.offered_by) {
+                (sent, Some(offered_by)) if offered_by == local && sent != Some(local) => {
+                    current.send(&Message::Draw { action: DrawAction::Offer, turn: local });
+                }
+                (Some(sent), None) if sent != local => {
+                    let action = if *board.get_turn() == Turn::Draw { DrawAction::Accept } else { DrawAction::Decline };
+                    current.send(&Message::Draw { action, turn: local });
+                }
+                _ => {}
+            }
+            *draw_sent = draw_offer.offered_by;
+        }
+    }
+    *last_turn = *board.get_turn();
+
+    if let (Some(guests), Some(token)) = (guests.as_mut(), token.as_ref()) {
+        let new_resign = resigned.filter(|_| !was_resigned);
+        if let Some(mut returning) = serve_guests(guests, &board, chat, rules, *resigned, new_resign, token) {
+            if connection.is_some() {
+                refuse(&mut returning, "the game is full".to_string());
+            } else {
+                catch_up(&mut returning, &board, rules, *resigned, Some(token.clone()));
+                *status = format!("Playing against {}", returning.peer());
+                *connection = Some(returning);
+                *resign_sent = true;
+                *synced = board.get_history().len();
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_overlay(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut overlay_query: Query<(&mut Visibility, &Children), With<WaitingOverlay>>,
+    mut network_text_query: Query<&mut Text, With<NetworkText>>,
+    mut text_query: Query<&mut Text, Without<NetworkText>>,
+    blocker_query: Query<Entity, With<BlockerMarker>>,
+    board: Res<Board>,
+    session: Res<NetworkSession>,
+) {
+    let playing = matches!(board.get_turn(), Turn::P1 | Turn::P2);
+    let waiting = session.local.is_some() && playing && (session.connection.is_none() || session.opponent_away);
+
+    for (mut visibility, children) in overlay_query.iter_mut() {
+        *visibility = if waiting { Visibility::Inherited } else { Visibility::Hidden };
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            text.sections[0].value = if session.connection.is_none() && session.address.is_some() {
+                "Reconnecting to the host...".to_string()
+            } else {
+                "Waiting for the opponent to reconnect...".to_string()
+            };
+        }
+    }
+
+    // Spectators never pick pieces, and players don't while their opponent is away
+    let blocked = session.local.is_none() || waiting;
+    match (blocked, blocker_query.get_single()) {
+        (true, Err(_)) => {
+            commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(shape::UVSphere { radius: 9.9, ..default() }.into()),
+                    material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.0).into()),
+                    ..default()
+                },
+                BlockerMarker,
+                NoBackfaceCulling,
+                PickBlocker,
+            ));
+        }
+        (false, Ok(entity)) => commands.entity(entity).despawn(),
+        _ => {}
+    }
+
+    let watching = session.guests.as_ref().map_or(0, |x| x.watching.len());
+    let text = match watching {
+        0 => session.status.clone(),
+        watching => format!("{}\n{} watching", session.status, watching),
+    };
+    for mut network_text in network_text_query.iter_mut() {
+        if network_text.sections[0].value != text {
+            network_text.sections[0].value = text.clone();
+        }
+    }
+}
+
+// Functions
+
+fn catch_up(
+    connection: &mut Connection,
+    board: &Board,
+    rules: &Rules,
+    resigned: Option<Turn>,
+    token: Option<String>,
+) {
+    connection.send(&Message::Welcome { token, version: PROTOCOL_VERSION });
+    connection.send(&Message::Start { rules: *rules });
+    connection.send(&Message::Resumed { plies: board.get_history().len() });
+    for (index, ply) in board.get_history().iter().enumerate() {
+        connection.send(&Message::Ply { index, ply: format_ply(ply) });
+    }
+    if let Some(turn) = resigned {
+        connection.send(&resignation(board, turn));
+    }
+    // Draws are replayed as accepted by the host, which is all a returning client or a spectator needs
+    if *board.get_turn() == Turn::Draw {
+        connection.send(&Message::Draw { action: DrawAction::Accept, turn: Turn::P1 });
+    }
+}
+
+fn connect(address: String) -> Task<io::Result<TcpStream>> {
+    AsyncComputeTaskPool::get().spawn(async move {
+        let address = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or(io::Error::new(io::ErrorKind::NotFound, "unknown address"))?;
+        TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)
+    })
+}
+
+fn handshake(
+    connection: &mut Connection,
+    host: bool,
+    kind: &JoinKind,
+    games: &mut Option<Vec<usize>>,
+    seat: &mut Option<Turn>,
+    token: &mut Option<String>,
+    rules: &Rules,
+) -> Result<Option<(Board, Rules, Option<Turn>)>, String> {
+    connection.poll().map_err(|x| format!("Connection lost: {}", x))?;
+
+    // Later messages stay queued on the connection, like the plies replayed for a spectator or a resumed game
+    while let Some(message) = connection.receive() {
+        match (message, host) {
+            (Message::List, true) => connection.send(&Message::Games { ids: vec![HOSTED_GAME] }),
+            (Message::Hello { version } | Message::Join { id: HOSTED_GAME, version }, true) if version == PROTOCOL_VERSION => {
+                // The host resolves a random first player so that both boards agree
+                let board = Board::new(rules);
+                let rules = Rules {
+                    first_player: if board.get_starter() == Turn::P2 { FirstPlayer::Silver } else { FirstPlayer::Gold },
+                    ..*rules
+                };
+                *token = Some(session_token().map_err(|x| format!("Couldn't create a session: {}", x))?);
+
+                connection.send(&Message::Welcome { token: token.clone(), version: PROTOCOL_VERSION });
+                connection.send(&Message::Start { rules });
+                return Ok(Some((board, rules, Some(Turn::P1))));
+            }
+            (Message::Join { id, version: _ }, true) if id != HOSTED_GAME => {
+                refuse(connection, format!("there's no open game {}", id));
+                return Err(format!("Turned away a client joining game {}", id));
+            }
+            (Message::Hello { version } | Message::Join { id: _, version }, true) => {
+                refuse(connection, format!("unsupported protocol version {}", version));
+                return Err(format!("Rejected a client with protocol version {}", version));
+            }
+            (Message::Resume { .. }, true) => {
+                refuse(connection, "there's no game to resume".to_string());
+                return Err("Turned away a client resuming another game".to_string());
+            }
+            (Message::Watch { version: _ }, true) => {
+                refuse(connection, "the game hasn't started yet".to_string());
+                return Err("Turned away an early spectator".to_string());
+            }
+            (Message::Games { ids }, false) => *games = Some(ids),
+            (Message::Welcome { token: welcome_token, version: _ }, false) => *token = welcome_token,
+            (Message::Seat { turn }, false) => *seat = Some(turn),
+            (Message::Start { rules }, false) => {
+                let local = if matches!(kind, JoinKind::Watch) { None } else { Some(seat.unwrap_or(Turn::P2)) };
+                return Ok(Some((Board::new(&rules), rules, local)));
+            }
+            (Message::Error { reason }, _) => return Err(format!("The host refused: {}", reason)),
+            _ => return Err("Received an unexpected message".to_string()),
+        }
+    }
+    Ok(None)
+}
+
+fn listen(port: u16) -> io::Result<TcpListener> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn receive(
+    connection: &mut Connection,
+    board: &mut Board,
+    chat: &mut Vec<ChatLine>,
+    draw_offer: &mut DrawOffer,
+    local: Option<Turn>,
+    opponent_away: &mut bool,
+    resigned: &mut Option<Turn>,
+    resuming: &mut Option<usize>,
+    synced: &mut usize,
+) -> io::Result<()> {
+    connection.poll()?;
+
+    while let Some(message) = connection.receive() {
+        match message {
+            Message::Ply { index, ply } => {
+                if index != *synced {
+                    return Err(refuse(connection, format!("ply {} is out of order", index)));
+                }
+
+                // Replayed plies must match the ones already played here
+                if let Some(known) = board.get_history().get(index) {
+                    if format_ply(known) != ply {
+                        return Err(refuse(connection, format!("ply {} doesn't match", index)));
+                    }
+                } else {
+                    // Players only accept their opponent's plies unless the host is replaying the game,
+                    // spectators accept both sides
+                    if resuming.is_none() && local.is_some_and(|x| x != board.get_turn().opponent()) {
+                        return Err(refuse(connection, format!("ply {} is out of turn", index)));
+                    }
+                    let ply = parse_ply(board, &ply).map_err(|x| refuse(connection, x))?;
+                    board.apply(&ply);
+                }
+
+                *synced += 1;
+                if resuming.is_some_and(|x| *synced >= x) {
+                    *resuming = None;
+                }
+            }
+            // Players only hear from their opponent, so chat is stamped with the opponent's seat whatever it
+            // claims, and the host relays it to spectators that way. Spectators hear both sides
+            Message::Chat { text, turn } => {
+                chat.push(ChatLine { emote: false, text, turn: local.map_or(turn, Turn::opponent) });
+            }
+            Message::Emote { name, turn } => {
+                chat.push(ChatLine { emote: true, text: name, turn: local.map_or(turn, Turn::opponent) });
+            }
+            Message::Draw { action: DrawAction::Offer, turn } => if local != Some(turn) && matches!(board.get_turn(), Turn::P1 | Turn::P2) {
+                draw_offer.offered_by = Some(turn);
+            },
+            // Players only take answers to their own offers, spectators follow along
+            Message::Draw { action, turn } => {
+                let answered = draw_offer.offered_by.is_some() && draw_offer.offered_by == local;
+                if local != Some(turn) && (local.is_none() || answered) {
+                    if action == DrawAction::Accept {
+                        board.draw();
+                    }
+                    draw_offer.offered_by = None;
+                }
+            }
+            Message::Resign { reason, turn } => if local != Some(turn) && matches!(board.get_turn(), Turn::P1 | Turn::P2) {
+                board.concede(turn, reason);
+                *resigned = Some(turn);
+            },
+            // Spectators don't wait for anyone
+            Message::Away { turn } => if local.is_some_and(|x| x != turn) {
+                *opponent_away = true;
+            },
+            Message::Back { turn } => if local.is_some_and(|x| x != turn) {
+                *opponent_away = false;
+            },
+            Message::Resumed { plies } => {
+                *synced = 0;
+                *resuming = (plies > 0).then_some(plies);
+            }
+            Message::Seat { .. } | Message::Start { .. } | Message::Welcome { .. } => {}
+            Message::Error { reason } => return Err(io::Error::other(reason)),
+            _ => return Err(refuse(connection, "unexpected message".to_string())),
+        }
+    }
+    Ok(())
+}
+
+fn refuse(connection: &mut Connection, reason: String) -> io::Error {
+    connection.send(&Message::Error { reason: reason.clone() });
+    let _ = connection.poll();
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+// Forfeits keep the reason the board recorded for them
+fn resignation(board: &Board, turn: Turn) -> Message {
+    Message::Resign {
+        reason: board.get_result().map_or(WinReason::Resignation, |x| x.reason),
+        turn,
+    }
+}
+
+fn serve_guests(
+    guests: &mut Guests,
+    board: &Board,
+    chat: &[ChatLine],
+    rules: &Rules,
+    resigned: Option<Turn>,
+    new_resign: Option<Turn>,
+    token: &str,
+) -> Option<Connection> {
+    for connection in guests.watching.iter_mut() {
+        for (index, ply) in board.get_history().iter().enumerate().skip(guests.broadcast) {
+            connection.send(&Message::Ply { index, ply: format_ply(ply) });
+        }
+        if let Some(turn) = new_resign {
+            connection.send(&resignation(board, turn));
+        }
+        if *board.get_turn() == Turn::Draw && !guests.draw_broadcast {
+            connection.send(&Message::Draw { action: DrawAction::Accept, turn: Turn::P1 });
+        }
+        for line in chat.iter().skip(guests.chat_broadcast) {
+            connection.send(&line.message());
+        }
+    }
+    guests.broadcast = board.get_history().len();
+    guests.chat_broadcast = chat.len();
+    guests.draw_broadcast = *board.get_turn() == Turn::Draw;
+    guests.watching.retain_mut(|x| {
+        while x.receive().is_some() {}
+        x.poll().is_ok()
+    });
+
+    loop {
+        match guests.listener.accept().and_then(|(x, _)| Connection::new(x)) {
+            Ok(connection) => guests.pending.push(connection),
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+            Err(error) => {
+                warn!("Couldn't accept a connection: {}", error);
+                break;
+            }
+        }
+    }
+
+    let mut returning = None;
+    for mut connection in mem::take(&mut guests.pending) {
+        if connection.poll().is_err() {
+            continue;
+        }
+        match connection.receive() {
+            None => guests.pending.push(connection),
+            Some(Message::Resume { token: resume_token, version }) if version == PROTOCOL_VERSION && resume_token == token => {
+                returning = Some(connection);
+            }
+            Some(Message::Resume { .. }) => {
+                refuse(&mut connection, "unknown session".to_string());
+            }
+            Some(Message::Watch { version }) if version == PROTOCOL_VERSION => {
+                // New spectators catch up on the whole game before following it live
+                catch_up(&mut connection, board, rules, resigned, None);
+                if connection.poll().is_ok() {
+                    guests.watching.push(connection);
+                }
+            }
+            Some(Message::Watch { version }) => {
+                refuse(&mut connection, format!("unsupported protocol version {}", version));
+            }
+            Some(_) => {
+                refuse(&mut connection, "the game is full".to_string());
+            }
+        }
+    }
+    returning
+}
\ No newline at end of file
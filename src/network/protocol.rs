@@ -0,0 +1,427 @@
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read, Write},
+    net::TcpStream,
+};
+
+use crate::{
+    board::{FirstPlayer, PlacementOrder, Rules, TimeControl, Turn, WinReason},
+    notation::{format_turn, format_win_reason, parse_turn, parse_win_reason},
+};
+
+// Constants
+
+pub const DEFAULT_PORT: u16 = 7878;
+// Longer lines can't be a valid message, the connection is dropped rather than buffering them
+const MAX_LINE_LENGTH: usize = 4096;
+pub const PROTOCOL_VERSION: u32 = 1;
+
+// Structs
+
+pub struct Connection {
+    inbox: VecDeque<Message>,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    stream: TcpStream,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            inbox: VecDeque::new(),
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            stream,
+        })
+    }
+    pub fn peer(&self) -> String {
+        self.stream.peer_addr().map(|x| x.to_string()).unwrap_or_default()
+    }
+    pub fn poll(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => { self.outgoing.drain(..written); }
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        let mut buffer = [0; 1024];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(ErrorKind::ConnectionAborted.into()),
+                Ok(read) => self.incoming.extend_from_slice(&buffer[..read]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        while let Some(end) = self.incoming.iter().position(|x| *x == b'\n') {
+            if end > MAX_LINE_LENGTH {
+                return Err(io::Error::new(ErrorKind::InvalidData, "line too long"));
+            }
+            let line: Vec<u8> = self.incoming.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if line.trim().is_empty() {
+                continue;
+            }
+            self.inbox.push_back(Message::parse(line.trim()).map_err(|x| io::Error::new(ErrorKind::InvalidData, x))?);
+        }
+        if self.incoming.len() > MAX_LINE_LENGTH {
+            return Err(io::Error::new(ErrorKind::InvalidData, "line too long"));
+        }
+        Ok(())
+    }
+    pub fn receive(&mut self) -> Option<Message> {
+        self.inbox.pop_front()
+    }
+    pub fn send(&mut self, message: &Message) {
+        self.outgoing.extend_from_slice(message.format().as_bytes());
+        self.outgoing.push(b'\n');
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DrawAction {
+    Accept,
+    Decline,
+    Offer,
+}
+
+#[derive(Clone)]
+pub enum Message {
+    // Servers tell players when their opponent's connection drops, and when they're back
+    Away {
+        turn: Turn,
+    },
+    Back {
+        turn: Turn,
+    },
+    Chat {
+        text: String,
+        turn: Turn,
+    },
+    // Offers are answered by the other player, accepting one ends the game
+    Draw {
+        action: DrawAction,
+        turn: Turn,
+    },
+    Emote {
+        name: String,
+        turn: Turn,
+    },
+    Error {
+        reason: String,
+    },
+    // Open games on a server
+    Games {
+        ids: Vec<usize>,
+    },
+    Hello {
+        version: u32,
+    },
+    Join {
+        id: usize,
+        version: u32,
+    },
+    List,
+    Ply {
+        index: usize,
+        ply: String,
+    },
+    // Ends the game in favour of the other player, for the given reason
+    Resign {
+        reason: WinReason,
+        turn: Turn,
+    },
+    Resume {
+        token: String,
+        version: u32,
+    },
+    // Followed by the given number of plies, replaying the game from the start
+    Resumed {
+        plies: usize,
+    },
+    // Servers tell each player their colour, clients of a hosted game always play silver
+    Seat {
+        turn: Turn,
+    },
+    // The first player is always resolved by the host
+    Start {
+        rules: Rules,
+    },
+    Watch {
+        version: u32,
+    },
+    // Players get a session token to resume the game with after losing the connection
+    Welcome {
+        token: Option<String>,
+        version: u32,
+    },
+}
+
+impl Message {
+    pub fn format(&self) -> String {
+        match self {
+            Message::Away { turn } => format!("AWAY {}", format_turn(*turn)),
+            Message::Back { turn } => format!("BACK {}", format_turn(*turn)),
+            Message::Chat { text, turn } => format!("CHAT {} {}", format_turn(*turn), text),
+            Message::Draw { action, turn } => format!(
+                "DRAW {} {}",
+                format_turn(*turn),
+                match action {
+                    DrawAction::Accept => "accept",
+                    DrawAction::Decline => "decline",
+                    DrawAction::Offer => "offer",
+                },
+            ),
+            Message::Emote { name, turn } => format!("EMOTE {} {}", format_turn(*turn), name),
+            Message::Error { reason } => format!("ERROR {}", reason),
+            Message::Games { ids } => format!("GAMES{}", ids.iter().map(|x| format!(" {}", x)).collect::<String>()),
+            Message::Hello { version } => format!("HELLO santorini {}", version),
+            Message::Join { id, version } => format!("JOIN santorini {} {}", version, id),
+            Message::List => "LIST".to_string(),
+            Message::Ply { index, ply } => format!("PLY {} {}", index, ply),
+            Message::Resign { reason, turn } => format!("RESIGN {} {}", format_turn(*turn), format_win_reason(*reason)),
+            Message::Resume { token, version } => format!("RESUME santorini {} {}", version, token),
+            Message::Resumed { plies } => format!("RESUMED {}", plies),
+            Message::Seat { turn } => format!("SEAT {}", format_turn(*turn)),
+            Message::Start { rules } => format!(
+                "START supply={} domes={} placement={} starter={} clock={}",
+                rules.limited_supply as u8,
+                rules.domes_anywhere as u8,
+                match rules.placement_order {
+                    PlacementOrder::Standard => "standard",
+                    PlacementOrder::Alternating => "alternating",
+                    PlacementOrder::StarterLast => "starter-last",
+                },
+                format_turn(if rules.first_player == FirstPlayer::Silver { Turn::P2 } else { Turn::P1 }),
+                match rules.time_control {
+                    TimeControl::Unlimited => "none".to_string(),
+                    TimeControl::Fischer { base, increment } => format!("fischer:{}:{}", base, increment),
+                    TimeControl::ByoYomi { base, period, periods } => format!("byoyomi:{}:{}:{}", base, period, periods),
+                },
+            ),
+            Message::Watch { version } => format!("WATCH santorini {}", version),
+            Message::Welcome { token: Some(token), version } => format!("WELCOME {} {}", version, token),
+            Message::Welcome { token: None, version } => format!("WELCOME {}", version),
+        }
+    }
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let (command, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        let arguments: Vec<&str> = arguments.split_whitespace().collect();
+        let invalid = || format!("invalid message \"{}\"", line);
+
+        match (command, arguments.as_slice()) {
+            ("AWAY", [turn]) => Ok(Message::Away { turn: parse_turn(turn)? }),
+            ("BACK", [turn]) => Ok(Message::Back { turn: parse_turn(turn)? }),
+            ("CHAT", [turn, text @ ..]) => Ok(Message::Chat {
+                text: text.join(" "),
+                turn: parse_turn(turn)?,
+            }),
+            ("DRAW", [turn, action]) => Ok(Message::Draw {
+                action: match *action {
+                    "accept" => DrawAction::Accept,
+                    "decline" => DrawAction::Decline,
+                    "offer" => DrawAction::Offer,
+                    _ => return Err(invalid()),
+                },
+                turn: parse_turn(turn)?,
+            }),
+            ("EMOTE", [turn, name]) => Ok(Message::Emote {
+                name: name.to_string(),
+                turn: parse_turn(turn)?,
+            }),
+            ("ERROR", _) => Ok(Message::Error { reason: arguments.join(" ") }),
+            ("GAMES", ids) => Ok(Message::Games {
+                ids: ids.iter().map(|x| x.parse()).collect::<Result<_, _>>().map_err(|_| invalid())?,
+            }),
+            ("HELLO", ["santorini", version]) => Ok(Message::Hello { version: version.parse().map_err(|_| invalid())? }),
+            ("JOIN", ["santorini", version, id]) => Ok(Message::Join {
+                id: id.parse().map_err(|_| invalid())?,
+                version: version.parse().map_err(|_| invalid())?,
+            }),
+            ("LIST", []) => Ok(Message::List),
+            ("PLY", [index, ply]) => Ok(Message::Ply {
+                index: index.parse().map_err(|_| invalid())?,
+                ply: ply.to_string(),
+            }),
+            ("RESIGN", [turn, reason @ ..]) if reason.len() <= 1 => Ok(Message::Resign {
+                reason: reason.first().map_or(Ok(WinReason::Resignation), |x| parse_win_reason(x))?,
+                turn: parse_turn(turn)?,
+            }),
+            ("RESUME", ["santorini", version, token]) => Ok(Message::Resume {
+                token: token.to_string(),
+                version: version.parse().map_err(|_| invalid())?,
+            }),
+            ("RESUMED", [plies]) => Ok(Message::Resumed { plies: plies.parse().map_err(|_| invalid())? }),
+            ("SEAT", [turn]) => Ok(Message::Seat { turn: parse_turn(turn)? }),
+            ("START", settings) => {
+                let mut rules = Rules::default();
+                for setting in settings {
+                    match setting.split_once('=').ok_or_else(invalid)? {
+                        ("clock", value) => rules.time_control = parse_time_control(value).ok_or_else(invalid)?,
+                        ("domes", value) => rules.domes_anywhere = value == "1",
+                        ("placement", "standard") => rules.placement_order = PlacementOrder::Standard,
+                        ("placement", "alternating") => rules.placement_order = PlacementOrder::Alternating,
+                        ("placement", "starter-last") => rules.placement_order = PlacementOrder::StarterLast,
+                        ("starter", "gold") => rules.first_player = FirstPlayer::Gold,
+                        ("starter", "silver") => rules.first_player = FirstPlayer::Silver,
+                        ("supply", value) => rules.limited_supply = value == "1",
+                        // Unknown settings are skipped so that newer hosts stay compatible
+                        _ => {}
+                    }
+                }
+                Ok(Message::Start { rules })
+            }
+            ("WATCH", ["santorini", version]) => Ok(Message::Watch { version: version.parse().map_err(|_| invalid())? }),
+            ("WELCOME", [version, token @ ..]) if token.len() <= 1 => Ok(Message::Welcome {
+                token: token.first().map(|x| x.to_string()),
+                version: version.parse().map_err(|_| invalid())?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// Functions
+
+fn parse_time_control(text: &str) -> Option<TimeControl> {
+    let fields: Vec<&str> = text.split(':').collect();
+    match fields.as_slice() {
+        ["none"] => Some(TimeControl::Unlimited),
+        ["fischer", base, increment] => Some(TimeControl::Fischer {
+            base: base.parse().ok()?,
+            increment: increment.parse().ok()?,
+        }),
+        ["byoyomi", base, period, periods] => Some(TimeControl::ByoYomi {
+            base: base.parse().ok()?,
+            period: period.parse().ok()?,
+            periods: periods.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+// Tokens let a player take their seat back, so they come from the OS rather than a guessable generator
+pub fn session_token() -> io::Result<String> {
+    let mut bytes = [0; 16];
+    getrandom::getrandom(&mut bytes).map_err(|x| io::Error::other(x.to_string()))?;
+    Ok(bytes.iter().map(|x| format!("{:02x}", x)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, thread, time::Duration};
+
+    // Both ends of a localhost socket, the first one wrapped in a connection
+    fn connected() -> (Connection, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let remote = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        (Connection::new(stream).unwrap(), remote)
+    }
+
+    fn poll_until_error(connection: &mut Connection) -> io::Error {
+        for _ in 0..100 {
+            if let Err(error) = connection.poll() {
+                return error;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("the connection never failed");
+    }
+
+    #[test]
+    fn messages_survive_formatting_and_parsing() {
+        let rules = Rules {
+            domes_anywhere: true,
+            first_player: FirstPlayer::Silver,
+            limited_supply: true,
+            placement_order: PlacementOrder::StarterLast,
+            time_control: TimeControl::ByoYomi { base: 300, period: 30, periods: 3 },
+        };
+        let messages = [
+            Message::Away { turn: Turn::P1 },
+            Message::Back { turn: Turn::P2 },
+            Message::Chat { text: "good game".to_string(), turn: Turn::P2 },
+            Message::Draw { action: DrawAction::Decline, turn: Turn::P1 },
+            Message::Emote { name: "wave".to_string(), turn: Turn::P1 },
+            Message::Error { reason: "the game is full".to_string() },
+            Message::Games { ids: vec![1, 4] },
+            Message::Games { ids: Vec::new() },
+            Message::Hello { version: PROTOCOL_VERSION },
+            Message::Join { id: 3, version: PROTOCOL_VERSION },
+            Message::List,
+            Message::Ply { index: 5, ply: "c3d4-e5".to_string() },
+            Message::Resign { reason: WinReason::Timeout, turn: Turn::P2 },
+            Message::Resume { token: "0123abcd".to_string(), version: PROTOCOL_VERSION },
+            Message::Resumed { plies: 12 },
+            Message::Seat { turn: Turn::P2 },
+            Message::Start { rules },
+            Message::Start { rules: Rules { time_control: TimeControl::Fischer { base: 60, increment: 2 }, ..Rules::default() } },
+            Message::Watch { version: PROTOCOL_VERSION },
+            Message::Welcome { token: Some("0123abcd".to_string()), version: PROTOCOL_VERSION },
+            Message::Welcome { token: None, version: PROTOCOL_VERSION },
+        ];
+
+        for message in messages {
+            let line = message.format();
+            assert_eq!(Message::parse(&line).map(|x| x.format()), Ok(line));
+        }
+        assert_eq!(
+            Message::Start { rules }.format(),
+            "START supply=1 domes=1 placement=starter-last starter=silver clock=byoyomi:300:30:3",
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_refused() {
+        for line in [
+            "",
+            "HELLO",
+            "HELLO chess 1",
+            "HELLO santorini one",
+            "JOIN santorini 1",
+            "LIST games",
+            "PLY c3",
+            "PLY first c3",
+            "SEAT bronze",
+            "DRAW gold maybe",
+            "RESIGN gold timeout twice",
+            "GAMES 1 two",
+            "START clock",
+            "START clock=fischer:60",
+            "WELCOME 1 token extra",
+            "MOVE c3",
+        ] {
+            assert!(Message::parse(line).is_err(), "\"{}\" was accepted", line);
+        }
+    }
+
+    #[test]
+    fn overlong_lines_drop_the_connection() {
+        let (mut connection, mut remote) = connected();
+        remote.write_all(b"PLY 0 c3\n").unwrap();
+        remote.write_all(&vec![b'x'; MAX_LINE_LENGTH + 1]).unwrap();
+
+        let error = poll_until_error(&mut connection);
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(matches!(connection.receive(), Some(Message::Ply { index: 0, .. })));
+    }
+
+    #[test]
+    fn malformed_lines_drop_the_connection() {
+        let (mut connection, mut remote) = connected();
+        remote.write_all(b"PLY first c3\n").unwrap();
+
+        let error = poll_until_error(&mut connection);
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(connection.receive().is_none());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,322 @@
+use std::{
+    io,
+    iter,
+    mem,
+    thread,
+    time::Duration,
+};
+
+use super::{
+    listen,
+    protocol::{Connection, DrawAction, Message, PROTOCOL_VERSION, session_token},
+    refuse,
+    resignation,
+};
+use crate::{
+    board::{Board, FirstPlayer, Rules, Turn},
+    notation::{format_ply, format_turn, parse_ply},
+};
+
+// The server pairs players and relays their plies, checking each one against its own board so that
+// clients don't have to trust each other:
+//
+// HELLO santorini <version>          -> joins the oldest open game, or opens a new one
+// JOIN santorini <version> <id>      -> joins the given open game
+// LIST                               -> GAMES <id>...
+// RESUME santorini <version> <token> -> takes a disconnected player's seat back
+// WATCH santorini <version>          -> follows the oldest game in progress
+//
+// Paired players get WELCOME, SEAT and START, after which the game goes on as with a hosted game. They
+// also hear AWAY <seat> when their opponent's connection drops, and BACK <seat> once they resumed
+
+// Constants
+
+const TICK: Duration = Duration::from_millis(10);
+
+// Structs
+
+struct Game {
+    board: Board,
+    draw_offer: Option<Turn>,
+    id: usize,
+    players: Vec<Player>,
+    resigned: Option<Turn>,
+    rules: Rules,
+    spectators: Vec<Connection>,
+}
+
+impl Game {
+    fn new(id: usize, connection: Connection, token: String) -> Self {
+        // The first player is resolved here so that every board agrees
+        let rules = Rules::default();
+        let board = Board::new(&rules);
+        let rules = Rules {
+            first_player: if board.get_starter() == Turn::P2 { FirstPlayer::Silver } else { FirstPlayer::Gold },
+            ..rules
+        };
+        println!("Game {}: opened by {}", id, connection.peer());
+
+        Self {
+            board,
+            draw_offer: None,
+            id,
+            players: vec![Player::new(connection, token, Turn::P1)],
+            resigned: None,
+            rules,
+            spectators: Vec::new(),
+        }
+    }
+    fn catch_up(&self, connection: &mut Connection, player: Option<&Player>) {
+        connection.send(&Message::Welcome { token: player.map(|x| x.token.clone()), version: PROTOCOL_VERSION });
+        if let Some(player) = player {
+            connection.send(&Message::Seat { turn: player.turn });
+        }
+        connection.send(&Message::Start { rules: self.rules });
+        connection.send(&Message::Resumed { plies: self.board.get_history().len() });
+        for (index, ply) in self.board.get_history().iter().enumerate() {
+            connection.send(&Message::Ply { index, ply: format_ply(ply) });
+        }
+        if let Some(turn) = self.resigned {
+            connection.send(&resignation(&self.board, turn));
+        }
+        if *self.board.get_turn() == Turn::Draw {
+            let turn = player.map_or(Turn::P1, |x| x.turn.opponent());
+            connection.send(&Message::Draw { action: DrawAction::Accept, turn });
+        }
+        for away in self.players.iter().filter(|x| x.connection.is_none() && Some(x.turn) != player.map(|x| x.turn)) {
+            connection.send(&Message::Away { turn: away.turn });
+        }
+    }
+    fn handle(&mut self, turn: Turn, message: Message) -> Result<(), String> {
+        let playing = matches!(self.board.get_turn(), Turn::P1 | Turn::P2);
+        match message {
+            Message::Ply { index, ply } => {
+                if self.is_open() || index != self.board.get_history().len() || *self.board.get_turn() != turn {
+                    return Err(format!("ply {} is out of turn", index));
+                }
+                let ply = parse_ply(&self.board, &ply)?;
+                self.board.apply(&ply);
+                self.board.check_win();
+                self.relay(turn, &Message::Ply { index, ply: format_ply(&ply) });
+            }
+            // Chat is relayed as coming from the sender's seat whatever it claims
+            Message::Chat { text, turn: _ } => self.relay(turn, &Message::Chat { text, turn }),
+            Message::Emote { name, turn: _ } => self.relay(turn, &Message::Emote { name, turn }),
+            Message::Draw { action: DrawAction::Offer, turn: _ } => if playing {
+                self.draw_offer = Some(turn);
+                self.relay(turn, &Message::Draw { action: DrawAction::Offer, turn });
+            },
+            // Answers only count for the opponent's offer
+            Message::Draw { action, turn: _ } => if playing && self.draw_offer == Some(turn.opponent()) {
+                if action == DrawAction::Accept {
+                    self.board.draw();
+                }
+                self.draw_offer = None;
+                self.relay(turn, &Message::Draw { action, turn });
+            },
+            // Repeated resigns are expected after a reconnection
+            Message::Resign { reason, turn: resigning } => if resigning == turn && playing {
+                self.board.concede(turn, reason);
+                self.resigned = Some(turn);
+                self.relay(turn, &Message::Resign { reason, turn });
+            },
+            _ => return Err("unexpected message".to_string()),
+        }
+        Ok(())
+    }
+    fn is_open(&self) -> bool {
+        self.players.len() < 2
+    }
+    fn join(&mut self, connection: Connection, token: String) {
+        println!("Game {}: joined by {}", self.id, connection.peer());
+        self.players.push(Player::new(connection, token, Turn::P2));
+
+        for player in self.players.iter_mut() {
+            if let Some(connection) = player.connection.as_mut() {
+                connection.send(&Message::Welcome { token: Some(player.token.clone()), version: PROTOCOL_VERSION });
+                connection.send(&Message::Seat { turn: player.turn });
+                connection.send(&Message::Start { rules: self.rules });
+            }
+        }
+    }
+    fn relay(&mut self, from: Turn, message: &Message) {
+        for player in self.players.iter_mut().filter(|x| x.turn != from) {
+            if let Some(connection) = player.connection.as_mut() {
+                connection.send(message);
+            }
+        }
+        for connection in self.spectators.iter_mut() {
+            connection.send(message);
+        }
+    }
+    fn resume(&mut self, mut connection: Connection, seat: usize) {
+        println!("Game {}: {} is back from {}", self.id, format_turn(self.players[seat].turn), connection.peer());
+        self.catch_up(&mut connection, Some(&self.players[seat]));
+        self.players[seat].connection = Some(connection);
+        self.relay(self.players[seat].turn, &Message::Back { turn: self.players[seat].turn });
+    }
+    fn update(&mut self) {
+        for seat in 0..self.players.len() {
+            let turn = self.players[seat].turn;
+            let Some(connection) = self.players[seat].connection.as_mut() else {
+                continue;
+            };
+
+            if let Err(error) = connection.poll() {
+                println!("Game {}: {} left ({})", self.id, format_turn(turn), error);
+                self.players[seat].connection = None;
+                self.relay(turn, &Message::Away { turn });
+                continue;
+            }
+            let messages: Vec<Message> = iter::from_fn(|| connection.receive()).collect();
+
+            for message in messages {
+                if let Err(reason) = self.handle(turn, message) {
+                    println!("Game {}: refused {} ({})", self.id, format_turn(turn), reason);
+                    if let Some(mut connection) = self.players[seat].connection.take() {
+                        refuse(&mut connection, reason);
+                    }
+                    self.relay(turn, &Message::Away { turn });
+                    break;
+                }
+            }
+        }
+
+        self.spectators.retain_mut(|x| {
+            while x.receive().is_some() {}
+            x.poll().is_ok()
+        });
+    }
+    fn watch(&mut self, mut connection: Connection) {
+        self.catch_up(&mut connection, None);
+        self.spectators.push(connection);
+    }
+}
+
+struct Player {
+    // None while the player is away, their seat is kept until they resume or the game is abandoned
+    connection: Option<Connection>,
+    token: String,
+    turn: Turn,
+}
+
+impl Player {
+    fn new(connection: Connection, token: String, turn: Turn) -> Self {
+        Self {
+            connection: Some(connection),
+            token,
+            turn,
+        }
+    }
+}
+
+// Functions
+
+pub fn run(port: u16) {
+    let listener = match listen(port) {
+        Ok(listener) => listener,
+        Err(error) => {
+            eprintln!("Couldn't listen on port {}: {}", port, error);
+            return;
+        }
+    };
+    println!("Listening on port {}", port);
+
+    let mut games: Vec<Game> = Vec::new();
+    let mut next_id = 1;
+    let mut pending: Vec<Connection> = Vec::new();
+    loop {
+        loop {
+            match listener.accept().and_then(|(x, _)| Connection::new(x)) {
+                Ok(connection) => pending.push(connection),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    eprintln!("Couldn't accept a connection: {}", error);
+                    break;
+                }
+            }
+        }
+
+        for mut connection in mem::take(&mut pending) {
+            if connection.poll().is_err() {
+                continue;
+            }
+            match connection.receive() {
+                None => pending.push(connection),
+                Some(Message::List) => {
+                    let ids = games.iter().filter(|x| x.is_open()).map(|x| x.id).collect();
+                    connection.send(&Message::Games { ids });
+                    pending.push(connection);
+                }
+                Some(Message::Hello { version }) if version == PROTOCOL_VERSION => {
+                    match (games.iter_mut().find(|x| x.is_open()), session_token()) {
+                        (_, Err(error)) => {
+                            refuse(&mut connection, format!("couldn't create a session: {}", error));
+                        }
+                        (Some(game), Ok(token)) => game.join(connection, token),
+                        (None, Ok(token)) => {
+                            games.push(Game::new(next_id, connection, token));
+                            next_id += 1;
+                        }
+                    }
+                }
+                Some(Message::Join { id, version }) if version == PROTOCOL_VERSION => {
+                    match (games.iter_mut().find(|x| x.id == id && x.is_open()), session_token()) {
+                        (None, _) => {
+                            refuse(&mut connection, format!("there's no open game {}", id));
+                        }
+                        (Some(_), Err(error)) => {
+                            refuse(&mut connection, format!("couldn't create a session: {}", error));
+                        }
+                        (Some(game), Ok(token)) => game.join(connection, token),
+                    }
+                }
+                Some(Message::Resume { token, version }) if version == PROTOCOL_VERSION => {
+                    let seat = games.iter_mut().find_map(|game| {
+                        let seat = game.players.iter().position(|x| x.token == token && x.connection.is_none())?;
+                        Some((game, seat))
+                    });
+                    match seat {
+                        Some((game, seat)) => game.resume(connection, seat),
+                        None => {
+                            refuse(&mut connection, "unknown session".to_string());
+                        }
+                    }
+                }
+                Some(Message::Watch { version }) if version == PROTOCOL_VERSION => {
+                    match games.iter_mut().find(|x| !x.is_open()) {
+                        Some(game) => game.watch(connection),
+                        None => {
+                            refuse(&mut connection, "there's no game to watch".to_string());
+                        }
+                    }
+                }
+                Some(
+                    Message::Hello { version }
+                    | Message::Join { id: _, version }
+                    | Message::Resume { token: _, version }
+                    | Message::Watch { version }
+                ) => {
+                    refuse(&mut connection, format!("unsupported protocol version {}", version));
+                }
+                Some(_) => {
+                    refuse(&mut connection, "unexpected message".to_string());
+                }
+            }
+        }
+
+        for game in games.iter_mut() {
+            game.update();
+        }
+        // Games are abandoned once nobody is left to resume them
+        games.retain(|game| {
+            let abandoned = game.players.iter().all(|x| x.connection.is_none());
+            if abandoned {
+                println!("Game {}: closed", game.id);
+            }
+            !abandoned
+        });
+
+        thread::sleep(TICK);
+    }
+}
\ No newline at end of file
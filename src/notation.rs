@@ -0,0 +1,219 @@
+use itertools::Itertools;
+
+use crate::board::{Action, Board, Piece, Ply, Turn, WinReason};
+
+// Squares are written as a column letter and a row number ("c3"), placements as a single square and
+// actions as "from-to+build", with a trailing "D" when a dome is built ("c3-d4+e5D")
+
+// Towers are written as their number of levels (4 being a complete tower) followed by "G" or "S" for
+// a worker on top or "D" for a dome on a lower level
+
+// Positions are written as "rows turn starter placements supply domes", for example
+// "0,0,0,0,0/0,1,2G,0,0/0,0,3D,0,0/0,0,1S,0,0/0,2G,0,0,2S gold gold - 21,17,14,17 0"
+
+// Functions
+
+pub fn format_ply(ply: &Ply) -> String {
+    match *ply {
+        Ply::Action(Action { from, to, build: None, dome: _ }) => {
+            format!("{}-{}", format_square(from.0, from.1), format_square(to.0, to.1))
+        }
+        Ply::Action(Action { from, to, build: Some(build), dome }) => format!(
+            "{}-{}+{}{}",
+            format_square(from.0, from.1),
+            format_square(to.0, to.1),
+            format_square(build.0, build.1),
+            if dome { "D" } else { "" },
+        ),
+        Ply::Placement { row, column } => format_square(row, column),
+    }
+}
+
+pub fn format_position(board: &Board) -> String {
+    let rows = (0..5)
+        .map(|row| (0..5).map(|column| format_tower(board, row, column)).join(","))
+        .join("/");
+    let placements = board.get_placements()
+        .iter()
+        .map(|x| if *x == Turn::P2 { 's' } else { 'g' })
+        .collect::<String>();
+    let supply = (1..5).map(|height| board.get_supply(height)).collect::<Option<Vec<usize>>>();
+
+    format!(
+        "{} {} {} {} {} {}",
+        rows,
+        format_turn(*board.get_turn()),
+        format_turn(board.get_starter()),
+        if placements.is_empty() { "-".to_string() } else { placements },
+        supply.map_or("-".to_string(), |x| x.iter().join(",")),
+        board.get_domes_anywhere() as u8,
+    )
+}
+
+pub fn format_square(row: usize, column: usize) -> String {
+    format!("{}{}", (b'a' + column as u8) as char, row + 1)
+}
+
+pub fn format_tower(board: &Board, row: usize, column: usize) -> String {
+    let mut tower = "0".to_string();
+    for height in 1..5 {
+        match board.get_piece(row, column, height) {
+            Some(Piece::Worker { turn: Turn::P1 }) => tower = format!("{}G", height - 1),
+            Some(Piece::Worker { turn: _ }) => tower = format!("{}S", height - 1),
+            Some(Piece::Dome) if height < 4 => tower = format!("{}D", height - 1),
+            Some(_) => tower = height.to_string(),
+            None => {}
+        }
+    }
+    tower
+}
+
+pub fn format_turn(turn: Turn) -> &'static str {
+    match turn {
+        Turn::P2 | Turn::WinP2 => "silver",
+        _ => "gold",
+    }
+}
+
+pub fn format_win_reason(reason: WinReason) -> &'static str {
+    match reason {
+        WinReason::Agreement => "agreement",
+        WinReason::Blocked => "blocked",
+        WinReason::Disconnect => "disconnect",
+        WinReason::Forfeit => "forfeit",
+        WinReason::Resignation => "resignation",
+        WinReason::Timeout => "timeout",
+        WinReason::Tower => "tower",
+    }
+}
+
+pub fn parse_ply(board: &Board, text: &str) -> Result<Ply, String> {
+    if board.is_placing() {
+        let (row, column) = parse_square(text)?;
+        if board.get_top(row, column).is_none() {
+            return Err(format!("\"{}\" is occupied", text));
+        }
+        return Ok(Ply::Placement { row, column });
+    }
+
+    let (squares, dome) = match text.strip_suffix('D') {
+        Some(squares) => (squares, true),
+        None => (text, false),
+    };
+    let (from, rest) = squares.split_once('-').ok_or(format!("\"{}\" isn't an action", text))?;
+    let (to, build) = match rest.split_once('+') {
+        Some((to, build)) => (to, Some(parse_square(build)?)),
+        None => (rest, None),
+    };
+    let (from, to) = (parse_square(from)?, parse_square(to)?);
+
+    board.get_actions()
+        .into_iter()
+        .find(|x| {
+            (x.from.0, x.from.1) == from
+                && (x.to.0, x.to.1) == to
+                && x.build.map(|(row, column, _)| (row, column)) == build
+                && x.dome == dome
+        })
+        .map(Ply::Action)
+        .ok_or(format!("\"{}\" isn't a legal action", text))
+}
+
+pub fn parse_position(text: &str) -> Result<Board, String> {
+    let fields = text.split_whitespace().collect_vec();
+    let [rows, turn, starter, placements, supply, domes] = fields.as_slice() else {
+        return Err(format!("\"{}\" isn't a position", text));
+    };
+
+    let mut board = Board::default();
+    let rows = rows.split('/').collect_vec();
+    if rows.len() != 5 {
+        return Err("a position needs 5 rows".to_string());
+    }
+    for (row, towers) in rows.into_iter().enumerate() {
+        let towers = towers.split(',').collect_vec();
+        if towers.len() != 5 {
+            return Err(format!("row {} needs 5 towers", row + 1));
+        }
+        for (column, tower) in towers.into_iter().enumerate() {
+            parse_tower(&mut board, row, column, tower)?;
+        }
+    }
+
+    let placements = match *placements {
+        "-" => Vec::new(),
+        placements => placements
+            .chars()
+            .map(|x| match x {
+                'g' => Ok(Turn::P1),
+                's' => Ok(Turn::P2),
+                _ => Err(format!("\"{}\" aren't placements", placements)),
+            })
+            .collect::<Result<Vec<Turn>, String>>()?,
+    };
+    let supply = match *supply {
+        "-" => None,
+        supply => Some(supply
+            .split(',')
+            .map(|x| x.parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .ok()
+            .and_then(|x| <[usize; 4]>::try_from(x).ok())
+            .ok_or(format!("\"{}\" isn't a supply", supply))?),
+    };
+
+    board.set_turn(parse_turn(turn)?);
+    board.set_placements(parse_turn(starter)?, placements);
+    board.set_supply(supply);
+    board.set_domes_anywhere(*domes == "1");
+    Ok(board)
+}
+
+pub fn parse_square(text: &str) -> Result<(usize, usize), String> {
+    match text.as_bytes() {
+        [column @ b'a'..=b'e', row @ b'1'..=b'5'] => Ok(((row - b'1') as usize, (column - b'a') as usize)),
+        _ => Err(format!("\"{}\" isn't a square", text)),
+    }
+}
+
+pub fn parse_tower(board: &mut Board, row: usize, column: usize, text: &str) -> Result<(), String> {
+    let invalid = || format!("\"{}\" isn't a tower", text);
+
+    let (height, top) = text.split_at(text.chars().next().map_or(0, char::len_utf8));
+    let height = match height.parse::<usize>() {
+        Ok(height) if height <= 4 => height,
+        _ => return Err(invalid()),
+    };
+    for level in 1..=height {
+        board.build(row, column, level);
+    }
+    match top {
+        "" => {}
+        "D" if height < 4 => board.build_dome(row, column, height + 1),
+        "G" if height < 4 => board.place_worker(row, column, height + 1, Turn::P1),
+        "S" if height < 4 => board.place_worker(row, column, height + 1, Turn::P2),
+        _ => return Err(invalid()),
+    }
+    Ok(())
+}
+
+pub fn parse_turn(text: &str) -> Result<Turn, String> {
+    match text {
+        "gold" => Ok(Turn::P1),
+        "silver" => Ok(Turn::P2),
+        _ => Err(format!("\"{}\" isn't a player", text)),
+    }
+}
+
+pub fn parse_win_reason(text: &str) -> Result<WinReason, String> {
+    match text {
+        "agreement" => Ok(WinReason::Agreement),
+        "blocked" => Ok(WinReason::Blocked),
+        "disconnect" => Ok(WinReason::Disconnect),
+        "forfeit" => Ok(WinReason::Forfeit),
+        "resignation" => Ok(WinReason::Resignation),
+        "timeout" => Ok(WinReason::Timeout),
+        "tower" => Ok(WinReason::Tower),
+        _ => Err(format!("\"{}\" isn't a reason", text)),
+    }
+}
\ No newline at end of file
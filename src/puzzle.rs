@@ -0,0 +1,282 @@
+use bevy::prelude::*;
+
+use bevy::asset::io::file::FileAssetReader;
+use itertools::Itertools;
+use std::{
+    fs,
+    io::Write,
+};
+
+use crate::{
+    AppState,
+    board::{Board, Turn, WinReason},
+    notation::{format_tower, format_turn, parse_tower},
+};
+
+pub struct PuzzlePlugin;
+impl Plugin for PuzzlePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame),
+                setup.run_if(resource_exists::<Puzzle>())
+            )
+            .add_systems(Update,
+                track_puzzle.run_if(in_state(AppState::InGame).and_then(resource_exists::<PuzzleProgress>()))
+            )
+            .add_systems(OnExit(AppState::InGame),
+                cleanup
+            );
+    }
+}
+
+// Constants
+
+const CUSTOM_PUZZLES_FILE: &str = "custom.txt";
+const PUZZLES_DIRECTORY: &str = "assets/puzzles";
+
+// Structs
+
+pub struct PuzzlePack {
+    pub name: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+// Resources
+
+#[derive(Clone, Resource)]
+pub struct Puzzle {
+    pub board: Board,
+    pub name: String,
+    pub turns: usize,
+}
+
+#[derive(Resource)]
+struct PuzzleProgress {
+    last_turn: Turn,
+    turns_played: usize,
+}
+
+// Components
+
+#[derive(Component)]
+struct PuzzleText;
+
+// Systems
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<PuzzleText>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    commands.remove_resource::<PuzzleProgress>();
+}
+
+fn setup(
+    mut commands: Commands,
+    puzzle: Res<Puzzle>,
+) {
+    commands.insert_resource(PuzzleProgress {
+        last_turn: *puzzle.board.get_turn(),
+        turns_played: 0,
+    });
+
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section("", TextStyle {
+                color: Color::WHITE,
+                font_size: 24.0,
+                ..default()
+            }),
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(65.0),
+                top: Val::Px(5.0),
+                ..default()
+            },
+            ..default()
+        },
+        PuzzleText,
+    ));
+}
+
+fn track_puzzle(
+    mut board: ResMut<Board>,
+    mut progress: ResMut<PuzzleProgress>,
+    mut text_query: Query<&mut Text, With<PuzzleText>>,
+    puzzle: Res<Puzzle>,
+) {
+    let attacker = *puzzle.board.get_turn();
+    let turn = *board.get_turn();
+
+    if turn != progress.last_turn {
+        if progress.last_turn == attacker {
+            progress.turns_played += 1;
+        }
+        progress.last_turn = turn;
+    }
+
+    if turn == attacker && progress.turns_played >= puzzle.turns {
+        board.forfeit(WinReason::Forfeit);
+    }
+
+    let status = match (attacker, *board.get_turn()) {
+        (Turn::P1, Turn::WinP1) | (Turn::P2, Turn::WinP2) => "Solved!".to_string(),
+        (_, Turn::WinP1 | Turn::WinP2) => "Failed, try again!".to_string(),
+        _ => format!("{} turns left", puzzle.turns.saturating_sub(progress.turns_played)),
+    };
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("{} (win in {})\n{}", puzzle.name, puzzle.turns, status);
+    }
+}
+
+// Functions
+
+pub fn export_puzzle(board: &Board, turns: usize) -> Result<Puzzle, String> {
+    let path = FileAssetReader::get_base_path().join(PUZZLES_DIRECTORY).join(CUSTOM_PUZZLES_FILE);
+    let count = fs::read_to_string(&path)
+        .map(|x| x.lines().filter(|x| x.trim_start().starts_with('[')).count())
+        .unwrap_or(0);
+    let puzzle = Puzzle {
+        board: board.clone(),
+        name: format!("Custom position {}", count + 1),
+        turns,
+    };
+
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| write!(file, "\n{}", format_puzzle(&puzzle)))
+        .map_err(|x| format!("{:?}: {}", path, x))?;
+
+    Ok(puzzle)
+}
+
+pub fn format_puzzle(puzzle: &Puzzle) -> String {
+    let mut text = format!(
+        "[{}]\nto-move: {}\nturns: {}\n",
+        puzzle.name,
+        format_turn(*puzzle.board.get_turn()),
+        puzzle.turns,
+    );
+
+    for row in 0..5 {
+        let squares = (0..5).map(|column| format!("{:<2}", format_tower(&puzzle.board, row, column)));
+        text += squares.collect_vec().join(" ").trim_end();
+        text.push('\n');
+    }
+
+    text
+}
+
+pub fn load_puzzle_packs() -> Vec<PuzzlePack> {
+    let directory = FileAssetReader::get_base_path().join(PUZZLES_DIRECTORY);
+    let Ok(entries) = fs::read_dir(&directory) else {
+        warn!("Couldn't read puzzles directory {:?}!", directory);
+        return Vec::new();
+    };
+
+    let mut packs = Vec::new();
+    for path in entries.filter_map(|x| x.ok()).map(|x| x.path()).sorted() {
+        if path.extension().is_none_or(|x| x != "txt") {
+            continue;
+        }
+
+        let name = path.file_stem().unwrap().to_string_lossy().replace('_', " ");
+        match fs::read_to_string(&path).map_err(|x| x.to_string()).and_then(|x| parse_puzzle_pack(&x)) {
+            Ok(puzzles) => packs.push(PuzzlePack { name, puzzles }),
+            Err(error) => warn!("Couldn't load puzzle pack {:?}: {}", path, error),
+        }
+    }
+    packs
+}
+
+pub fn parse_puzzle_pack(source: &str) -> Result<Vec<Puzzle>, String> {
+    let mut puzzles = Vec::new();
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, x)| (i + 1, x.split('#').next().unwrap().trim()))
+        .filter(|(_, x)| !x.is_empty());
+
+    while let Some((line_number, line)) = lines.next() {
+        let name = line
+            .strip_prefix('[')
+            .and_then(|x| x.strip_suffix(']'))
+            .ok_or(format!("line {}: expected a puzzle name in square brackets", line_number))?;
+
+        let mut board = Board::default();
+        let mut turns = None;
+        let mut row = 0;
+        while row < 5 {
+            let (line_number, line) = lines.next().ok_or(format!("puzzle \"{}\" is incomplete", name))?;
+
+            if let Some((key, value)) = line.split_once(':') {
+                match (key.trim(), value.trim()) {
+                    ("to-move", "gold") => board.set_turn(Turn::P1),
+                    ("to-move", "silver") => board.set_turn(Turn::P2),
+                    ("turns", value) => turns = Some(value.parse::<usize>()
+                        .map_err(|_| format!("line {}: invalid number of turns", line_number))?),
+                    _ => return Err(format!("line {}: unknown setting \"{}\"", line_number, line)),
+                }
+                continue;
+            }
+
+            let squares = line.split_whitespace().collect_vec();
+            if squares.len() != 5 {
+                return Err(format!("line {}: expected 5 squares", line_number));
+            }
+            for (column, square) in squares.into_iter().enumerate() {
+                parse_tower(&mut board, row, column, square)
+                    .map_err(|_| format!("line {}: invalid square \"{}\"", line_number, square))?;
+            }
+            row += 1;
+        }
+
+        puzzles.push(Puzzle {
+            board,
+            name: name.to_string(),
+            turns: turns.ok_or(format!("puzzle \"{}\" has no number of turns", name))?,
+        });
+    }
+
+    Ok(puzzles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_pack_parses() {
+        let puzzles = parse_puzzle_pack(include_str!("../assets/puzzles/basics.txt")).unwrap();
+        assert_eq!(puzzles.iter().map(|x| x.name.as_str()).collect_vec(), ["Top of the world", "Cornered", "Double threat"]);
+
+        let puzzle = &puzzles[0];
+        assert_eq!(puzzle.turns, 1);
+        assert_eq!(*puzzle.board.get_turn(), Turn::P1);
+        assert_eq!(format_tower(&puzzle.board, 1, 1), "2G");
+        assert_eq!(format_tower(&puzzle.board, 4, 0), "0S");
+
+        // Formatting a puzzle gives back the same puzzle
+        let again = parse_puzzle_pack(&format_puzzle(puzzle)).unwrap();
+        assert_eq!(format_puzzle(&again[0]), format_puzzle(puzzle));
+    }
+
+    #[test]
+    fn malformed_packs_are_refused() {
+        let rows = "0 0 0 0 0\n".repeat(5);
+        let error = |source: &str| parse_puzzle_pack(source).err().unwrap();
+
+        assert_eq!(error("Unnamed\n"), "line 1: expected a puzzle name in square brackets");
+        assert_eq!(error("[Short]\nturns: 1\n0 0 0 0 0\n"), "puzzle \"Short\" is incomplete");
+        assert_eq!(error(&format!("[Endless]\n{}", rows)), "puzzle \"Endless\" has no number of turns");
+        assert_eq!(error(&format!("[Many]\nturns: many\n{}", rows)), "line 2: invalid number of turns");
+        assert_eq!(error(&format!("[Rated]\nrating: 5\n{}", rows)), "line 2: unknown setting \"rating: 5\"");
+        assert_eq!(error("[Narrow]\nturns: 1\n0 0 0 0\n"), "line 3: expected 5 squares");
+        assert_eq!(error("[Tall]\nturns: 1\n0 0 9 0 0\n"), "line 3: invalid square \"9\"");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,72 @@
+use bevy::asset::io::file::FileAssetReader;
+use itertools::Itertools;
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    board::{Board, FirstPlayer, Rules, StartingPosition, Turn},
+    network::ChatLine,
+    notation::{format_ply, format_position, format_turn, format_win_reason},
+};
+
+// Replays are written as the position the game started from, the plies played from it, how and when
+// the game ended and the chat of network games, for example:
+//
+// position: 0,0,0,0,0/0,0,0,0,0/0,0,0,0,0/0,0,0,0,0/0,0,0,0,0 gold gold ggss - 0
+// plies: c3 d3 b2 d4 c3-c4+c5 d4-d5+e5
+// result: silver resignation
+// duration: 95
+// chat: gold Good game!
+
+// Constants
+
+const REPLAYS_DIRECTORY: &str = "replays";
+
+// Functions
+
+pub fn format_replay(start: &Board, board: &Board, duration: Duration, chat: &[ChatLine]) -> String {
+    let mut text = format!(
+        "position: {}\nplies: {}\n",
+        format_position(start),
+        board.get_history().iter().map(format_ply).join(" "),
+    );
+
+    if let Some(result) = board.get_result() {
+        let winner = result.winner.map_or("draw", format_turn);
+        text += &format!("result: {} {}\n", winner, format_win_reason(result.reason));
+    }
+    text += &format!("duration: {}\n", duration.as_secs());
+
+    for line in chat {
+        let kind = if line.emote { "emote" } else { "chat" };
+        text += &format!("{}: {} {}\n", kind, format_turn(line.turn), line.text);
+    }
+
+    text
+}
+
+// Games without a starting position were set up from the rules, with the first player they got
+pub fn starting_board(board: &Board, rules: &Rules, starting_position: Option<&StartingPosition>) -> Board {
+    match starting_position {
+        Some(starting_position) => starting_position.board.clone(),
+        None => Board::new(&Rules {
+            first_player: if board.get_starter() == Turn::P2 { FirstPlayer::Silver } else { FirstPlayer::Gold },
+            ..*rules
+        }),
+    }
+}
+
+pub fn save_replay(start: &Board, board: &Board, duration: Duration, chat: &[ChatLine]) -> Result<PathBuf, String> {
+    let directory = FileAssetReader::get_base_path().join(REPLAYS_DIRECTORY);
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |x| x.as_secs());
+    let path = directory.join(format!("replay_{}.txt", seconds));
+
+    fs::create_dir_all(&directory)
+        .and_then(|_| fs::write(&path, format_replay(start, board, duration, chat)))
+        .map_err(|x| format!("{:?}: {}", path, x))?;
+
+    Ok(path)
+}
\ No newline at end of file
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use bevy::asset::io::file::FileAssetReader;
+use std::fs;
+
+use crate::{
+    animation::AnimationSpeed,
+    camera::CameraSettings,
+    environment::GraphicsQuality,
+    flat_view::ViewMode,
+    labels::LabelSettings,
+    theme::Theme,
+};
+
+// Settings are kept between runs in a file next to the assets, one "key: value" per line:
+//
+// animations: normal
+// camera: fixed
+// graphics: medium
+// labels: on
+// theme: blue-orange
+// view: scene
+//
+// Unknown keys and values are skipped, so that files from other versions still load
+
+pub struct SettingsPlugin;
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Theme>()
+            .add_systems(Startup, load_settings)
+            .add_systems(Last, save_settings);
+    }
+}
+
+// Constants
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+// Systems
+
+fn load_settings(
+    mut animation_speed: ResMut<AnimationSpeed>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut graphics_quality: ResMut<GraphicsQuality>,
+    mut label_settings: ResMut<LabelSettings>,
+    mut theme: ResMut<Theme>,
+    mut view_mode: ResMut<ViewMode>,
+) {
+    let path = FileAssetReader::get_base_path().join(SETTINGS_FILE);
+    let Ok(source) = fs::read_to_string(&path) else {
+        return;
+    };
+
+    for line in source.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        let known = match key.trim() {
+            "animations" => parse_animation_speed(value).map(|x| *animation_speed = x),
+            "camera" => parse_toggle(value, "follow", "fixed").map(|x| camera_settings.follow_turn = x),
+            "graphics" => parse_graphics_quality(value).map(|x| *graphics_quality = x),
+            "labels" => parse_toggle(value, "on", "off").map(|x| label_settings.visible = x),
+            "theme" => parse_theme(value).map(|x| *theme = x),
+            "view" => parse_toggle(value, "flat", "scene")
+                .map(|x| *view_mode = if x { ViewMode::Flat } else { ViewMode::Scene }),
+            _ => None,
+        };
+        if known.is_none() {
+            warn!("Skipped setting \"{}\" in {:?}", line, path);
+        }
+    }
+}
+
+fn save_settings(
+    mut saved: Local<Option<String>>,
+    animation_speed: Res<AnimationSpeed>,
+    camera_settings: Res<CameraSettings>,
+    graphics_quality: Res<GraphicsQuality>,
+    label_settings: Res<LabelSettings>,
+    theme: Res<Theme>,
+    view_mode: Res<ViewMode>,
+) {
+    let changed = animation_speed.is_changed()
+        || camera_settings.is_changed()
+        || graphics_quality.is_changed()
+        || label_settings.is_changed()
+        || theme.is_changed()
+        || view_mode.is_changed();
+    if saved.is_some() && !changed {
+        return;
+    }
+
+    let text = format!(
+        "animations: {}\ncamera: {}\ngraphics: {}\nlabels: {}\ntheme: {}\nview: {}\n",
+        format_animation_speed(*animation_speed),
+        if camera_settings.follow_turn { "follow" } else { "fixed" },
+        format_graphics_quality(*graphics_quality),
+        if label_settings.visible { "on" } else { "off" },
+        format_theme(*theme),
+        if *view_mode == ViewMode::Flat { "flat" } else { "scene" },
+    );
+
+    // What was loaded at startup is taken as saved already
+    if saved.as_ref().is_some_and(|x| *x != text) {
+        let path = FileAssetReader::get_base_path().join(SETTINGS_FILE);
+        if let Err(error) = fs::write(&path, &text) {
+            warn!("Couldn't save settings to {:?}: {}", path, error);
+        }
+    }
+    *saved = Some(text);
+}
+
+// Functions
+
+fn format_animation_speed(animation_speed: AnimationSpeed) -> &'static str {
+    match animation_speed {
+        AnimationSpeed::Normal => "normal",
+        AnimationSpeed::Fast => "fast",
+        AnimationSpeed::Off => "off",
+    }
+}
+
+fn format_graphics_quality(graphics_quality: GraphicsQuality) -> &'static str {
+    match graphics_quality {
+        GraphicsQuality::Low => "low",
+        GraphicsQuality::Medium => "medium",
+        GraphicsQuality::High => "high",
+    }
+}
+
+fn format_theme(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Classic => "classic",
+        Theme::BlueOrange => "blue-orange",
+        Theme::RedTeal => "red-teal",
+        Theme::Contrast => "contrast",
+    }
+}
+
+fn parse_animation_speed(text: &str) -> Option<AnimationSpeed> {
+    [AnimationSpeed::Normal, AnimationSpeed::Fast, AnimationSpeed::Off]
+        .into_iter()
+        .find(|x| format_animation_speed(*x) == text)
+}
+
+fn parse_graphics_quality(text: &str) -> Option<GraphicsQuality> {
+    [GraphicsQuality::Low, GraphicsQuality::Medium, GraphicsQuality::High]
+        .into_iter()
+        .find(|x| format_graphics_quality(*x) == text)
+}
+
+fn parse_theme(text: &str) -> Option<Theme> {
+    Theme::ALL.into_iter().find(|x| format_theme(*x) == text)
+}
+
+fn parse_toggle(text: &str, on: &str, off: &str) -> Option<bool> {
+    match text {
+        _ if text == on => Some(true),
+        _ if text == off => Some(false),
+        _ => None,
+    }
+}
\ No newline at end of file
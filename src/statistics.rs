@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use bevy::asset::io::file::FileAssetReader;
+use std::{
+    collections::BTreeMap,
+    fs,
+};
+
+use crate::{
+    AppState,
+    board::{Board, StartingPosition},
+    controller::{Controller, Controllers},
+    network::NetworkSession,
+    notation::{format_turn, format_win_reason},
+    puzzle::Puzzle,
+};
+
+// Finished games are counted by result in a file next to the settings, one "winner reason: count" per line:
+//
+// draw agreement: 1
+// gold tower: 4
+// silver resignation: 2
+//
+// Only games someone played here are counted, so not spectated games, puzzles, or positions set up in the
+// editor, which is also where Analyze leads
+
+pub struct StatisticsPlugin;
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Statistics>()
+            .add_systems(Startup, load_statistics)
+            .add_systems(Update, record_result.run_if(in_state(AppState::InGame)));
+    }
+}
+
+// Constants
+
+const STATISTICS_FILE: &str = "statistics.txt";
+
+// Resources
+
+#[derive(Default, Resource)]
+struct Statistics {
+    // Results other versions wrote are kept as they are
+    results: BTreeMap<String, usize>,
+}
+
+// Systems
+
+fn load_statistics(
+    mut statistics: ResMut<Statistics>,
+) {
+    let path = FileAssetReader::get_base_path().join(STATISTICS_FILE);
+    let Ok(source) = fs::read_to_string(&path) else {
+        return;
+    };
+
+    for line in source.lines() {
+        match line.rsplit_once(':').map(|(key, value)| (key.trim(), value.trim().parse::<usize>())) {
+            Some((key, Ok(count))) => *statistics.results.entry(key.to_string()).or_default() += count,
+            _ => warn!("Skipped statistic \"{}\" in {:?}", line, path),
+        }
+    }
+}
+
+fn record_result(
+    mut recorded: Local<bool>,
+    mut statistics: ResMut<Statistics>,
+    board: Res<Board>,
+    controllers: Res<Controllers>,
+    network_session: Option<Res<NetworkSession>>,
+    puzzle: Option<Res<Puzzle>>,
+    starting_position: Option<Res<StartingPosition>>,
+) {
+    // A game in progress has no result, so the next one to end is counted again
+    let Some(result) = board.get_result() else {
+        *recorded = false;
+        return;
+    };
+    if *recorded {
+        return;
+    }
+    *recorded = true;
+
+    // Network sessions set a starting position too, but never an edited one
+    let played = controllers.p1 == Controller::Human || controllers.p2 == Controller::Human;
+    let edited = starting_position.is_some() && network_session.is_none();
+    if !played || edited || puzzle.is_some() {
+        return;
+    }
+
+    let key = format!("{} {}", result.winner.map_or("draw", format_turn), format_win_reason(result.reason));
+    *statistics.results.entry(key).or_default() += 1;
+
+    let text: String = statistics.results.iter().map(|(key, count)| format!("{}: {}\n", key, count)).collect();
+    let path = FileAssetReader::get_base_path().join(STATISTICS_FILE);
+    if let Err(error) = fs::write(&path, text) {
+        warn!("Couldn't save statistics to {:?}: {}", path, error);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::board::Turn;
+
+// Structs
+
+#[derive(Clone, Copy)]
+pub struct Palette {
+    pub block: Color,
+    pub dark_square: Color,
+    pub dome: Color,
+    pub light_square: Color,
+    // Classic workers are polished metal, the others are plain so their colours read true
+    pub metallic: bool,
+    pub player1: Color,
+    pub player2: Color,
+}
+
+impl Palette {
+    pub fn player(&self, turn: Turn) -> Color {
+        match turn {
+            Turn::P1 | Turn::WinP1 => self.player1,
+            Turn::P2 | Turn::WinP2 => self.player2,
+            Turn::Draw => self.block,
+        }
+    }
+}
+
+// Resources
+
+// The colour-blind palettes keep the players apart by lightness as well as hue, picked from the
+// Okabe-Ito colours
+#[derive(Clone, Copy, Default, PartialEq, Resource)]
+pub enum Theme {
+    #[default]
+    Classic,
+    // For red-green colour blindness
+    BlueOrange,
+    // For blue-yellow colour blindness
+    RedTeal,
+    Contrast,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [Theme::Classic, Theme::BlueOrange, Theme::RedTeal, Theme::Contrast];
+
+    pub fn palette(&self) -> Palette {
+        match self {
+            Theme::Classic => Palette {
+                block: Color::rgb_u8(250, 254, 255),
+                dark_square: Color::rgb_u8(65, 92, 224),
+                dome: Color::BLUE,
+                light_square: Color::rgb_u8(117, 205, 255),
+                metallic: true,
+                player1: Color::GOLD,
+                player2: Color::SILVER,
+            },
+            Theme::BlueOrange => Palette {
+                block: Color::rgb_u8(250, 250, 250),
+                dark_square: Color::rgb_u8(120, 120, 120),
+                dome: Color::rgb_u8(40, 40, 40),
+                light_square: Color::rgb_u8(180, 180, 180),
+                metallic: false,
+                player1: Color::rgb_u8(230, 159, 0),
+                player2: Color::rgb_u8(0, 114, 178),
+            },
+            Theme::RedTeal => Palette {
+                block: Color::rgb_u8(250, 250, 250),
+                dark_square: Color::rgb_u8(120, 120, 120),
+                dome: Color::rgb_u8(40, 40, 40),
+                light_square: Color::rgb_u8(180, 180, 180),
+                metallic: false,
+                player1: Color::rgb_u8(213, 94, 0),
+                player2: Color::rgb_u8(0, 158, 115),
+            },
+            Theme::Contrast => Palette {
+                block: Color::WHITE,
+                dark_square: Color::rgb_u8(30, 30, 30),
+                dome: Color::rgb_u8(204, 121, 167),
+                light_square: Color::rgb_u8(90, 90, 90),
+                metallic: false,
+                player1: Color::YELLOW,
+                player2: Color::rgb_u8(86, 180, 233),
+            },
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,168 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    process::{Child, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+// Headless clients talking to the server binary over real sockets
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Client {
+    reader: BufReader<TcpStream>,
+    stream: TcpStream,
+}
+
+impl Client {
+    fn connect(server: &Server) -> Self {
+        for _ in 0..100 {
+            if let Ok(stream) = TcpStream::connect(("127.0.0.1", server.port)) {
+                stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+                return Self { reader: BufReader::new(stream.try_clone().unwrap()), stream };
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("the server never started listening");
+    }
+    fn expect(&mut self, prefix: &str) -> String {
+        let line = self.receive();
+        assert!(line.starts_with(prefix), "expected \"{}\", got \"{}\"", prefix, line);
+        line
+    }
+    fn receive(&mut self) -> String {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        line.trim_end().to_string()
+    }
+    fn send(&mut self, line: &str) {
+        writeln!(self.stream, "{}", line).unwrap();
+    }
+}
+
+struct Server {
+    child: Child,
+    port: u16,
+}
+
+impl Server {
+    fn start() -> Self {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let child = Command::new(env!("CARGO_BIN_EXE_server"))
+            .arg(port.to_string())
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        Self { child, port }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Returns the token and the starting player from the WELCOME, SEAT and START lines
+fn join(client: &mut Client, seat: &str) -> (String, String) {
+    let token = client.expect("WELCOME 1 ")["WELCOME 1 ".len()..].to_string();
+    client.expect(&format!("SEAT {}", seat));
+    let start = client.expect("START ");
+    let starter = start.split_whitespace().find_map(|x| x.strip_prefix("starter=")).unwrap().to_string();
+    (token, starter)
+}
+
+#[test]
+fn plays_a_game_until_resignation() {
+    let server = Server::start();
+
+    let mut gold = Client::connect(&server);
+    gold.send("HELLO santorini 1");
+    let mut silver = Client::connect(&server);
+    silver.send("LIST");
+    silver.expect("GAMES 1");
+    silver.send("JOIN santorini 1 1");
+
+    let (_, starter) = join(&mut gold, "gold");
+    join(&mut silver, "silver");
+    let (starting, other) = if starter == "gold" { (&mut gold, &mut silver) } else { (&mut silver, &mut gold) };
+
+    starting.send("PLY 0 c3");
+    starting.send("PLY 1 d3");
+    assert_eq!(other.receive(), "PLY 0 c3");
+    assert_eq!(other.receive(), "PLY 1 d3");
+
+    // Plies out of turn are refused
+    starting.send("PLY 2 c4");
+    starting.expect("ERROR ");
+
+    let mut spectator = Client::connect(&server);
+    spectator.send("WATCH santorini 1");
+    spectator.expect("WELCOME 1");
+    spectator.expect("START ");
+    spectator.expect("RESUMED 2");
+    spectator.expect("PLY 0 c3");
+    spectator.expect("PLY 1 d3");
+    // The refused player was dropped
+    spectator.expect(&format!("AWAY {}", starter));
+
+    // Spectators then follow the game as it goes on
+    other.send("PLY 2 c4");
+    other.send(&format!("RESIGN {} resignation", if starter == "gold" { "silver" } else { "gold" }));
+    spectator.expect("PLY 2 c4");
+    spectator.expect("RESIGN ");
+}
+
+#[test]
+fn resumes_a_game_after_a_disconnection() {
+    let server = Server::start();
+
+    let mut gold = Client::connect(&server);
+    gold.send("HELLO santorini 1");
+    let mut silver = Client::connect(&server);
+    silver.send("HELLO santorini 1");
+
+    let (token, starter) = join(&mut gold, "gold");
+    join(&mut silver, "silver");
+    if starter == "gold" {
+        gold.send("PLY 0 c3");
+        silver.expect("PLY 0 c3");
+    } else {
+        silver.send("PLY 0 c3");
+        gold.expect("PLY 0 c3");
+    }
+    drop(gold);
+
+    // The opponent hears about the disconnection, and waits for the player to come back
+    silver.expect("AWAY gold");
+
+    // The seat is only free once the server noticed the disconnection
+    let mut gold = None;
+    for _ in 0..100 {
+        let mut client = Client::connect(&server);
+        client.send(&format!("RESUME santorini 1 {}", token));
+        if client.receive().starts_with("WELCOME") {
+            gold = Some(client);
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let mut gold = gold.expect("the seat was never given back");
+
+    gold.expect("SEAT gold");
+    gold.expect("START ");
+    gold.expect("RESUMED 1");
+    gold.expect("PLY 0 c3");
+    silver.expect("BACK gold");
+
+    // Play goes on with the resumed connection
+    if starter == "gold" {
+        gold.send("PLY 1 d3");
+        silver.expect("PLY 1 d3");
+    } else {
+        silver.send("PLY 1 d3");
+        gold.expect("PLY 1 d3");
+    }
+}
\ No newline at end of file